@@ -0,0 +1,6 @@
+use dq10tools::cli::{ast, Standalone};
+
+fn main() {
+    let cli = Standalone::<ast::Args>::init();
+    ast::run(cli.args, cli.format, cli.tz);
+}
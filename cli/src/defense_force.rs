@@ -0,0 +1,6 @@
+use dq10tools::cli::{defense_force, Standalone};
+
+fn main() {
+    let cli = Standalone::<defense_force::Args>::init();
+    defense_force::run(cli.args, cli.format, cli.tz);
+}
@@ -0,0 +1,6 @@
+use dq10tools::cli::{defense_force, Standalone};
+
+fn main() {
+    let cli = Standalone::<defense_force::ScheduleArgs>::init();
+    defense_force::run_schedule(cli.args, cli.format, cli.tz);
+}
@@ -0,0 +1,23 @@
+use clap::Parser;
+use dq10tools::cli;
+
+/// ドラクエ10のエンドコンテンツ情報を表示する
+#[derive(Parser, Debug)]
+#[command(name = "dq10tools", version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+
+    /// output format
+    #[arg(short='f', long, value_enum, global = true, default_value_t)]
+    format: cli::Format,
+
+    /// time zone to show and read date times in, such as Asia/Tokyo. "local" for the system time zone
+    #[arg(long, value_name = "TZ", global = true, default_value = "local")]
+    tz: dq10tools::zone::Zone,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    cli::run(cli.command, cli.format, cli.tz);
+}
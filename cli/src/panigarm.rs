@@ -0,0 +1,6 @@
+use dq10tools::cli::{panigarm, Standalone};
+
+fn main() {
+    let cli = Standalone::<panigarm::Args>::init();
+    panigarm::run(cli.args, cli.format, cli.tz);
+}
@@ -0,0 +1,7 @@
+use dq10tools::cli::{levels, Standalone};
+use dq10tools::saint_guardians::SaintGuardians;
+
+fn main() {
+    let cli = Standalone::<levels::Args>::init();
+    levels::run(&SaintGuardians, cli.args, cli.format, cli.tz);
+}
@@ -0,0 +1,7 @@
+use dq10tools::cli::{levels, Standalone};
+use dq10tools::sinners::Sinners;
+
+fn main() {
+    let cli = Standalone::<levels::Args>::init();
+    levels::run(&Sinners, cli.args, cli.format, cli.tz);
+}
@@ -1,29 +1,79 @@
-use chrono::{DateTime, NaiveTime, NaiveDateTime, Datelike, TimeZone, Timelike, Utc, FixedOffset};
+use std::ops::Add;
+use chrono::{DateTime, Duration, NaiveTime, NaiveDateTime, Datelike, TimeZone, Timelike, Utc, FixedOffset};
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeStruct;
+use crate::clock::Clock;
+use crate::error::{self, Error};
 
-#[derive(PartialEq, Eq, Debug)]
-pub struct AST(NaiveTime);
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+const NANOS_PER_DAY: i64 = 86_400 * NANOS_PER_SEC;
+/// アストルティアの時間は現実の20倍の速さで進む
+const SPEED: i64 = 20;
+
+/// アストルティア時刻。dayはJSTの0時から数えたアストルティアの日 (0から19)
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct AST {
+    time: NaiveTime,
+    day: u32,
+}
 
 impl AST {
     pub fn state(&self) -> State {
-        let h = self.0.hour();
+        let h = self.time.hour();
         if (6..18).contains(&h) {
             State::Day
         } else {
             State::Night
         }
     }
+    /// 次に朝と夜が切り替わるまでの現実の分数。分未満は四捨五入する
     pub fn state_change_in(&self) -> u32 {
-        let t = self.0;
-        let h = self.0.hour();
-        let dh = (29 - h) % 12 + 1;
-        ((dh * 3600 - t.minute() * 60 - t.second()) as f64 / 1200.0).round() as u32
+        let millis = self.until_state_change().num_milliseconds();
+        ((millis + 30_000) / 60_000) as u32
+    }
+    /// 次に朝と夜が切り替わるまでの現実の時間
+    pub fn until_state_change(&self) -> Duration {
+        let half_day = NANOS_PER_DAY / 2;
+        let six = NANOS_PER_DAY / 4;
+        let remaining = half_day - (self.nanos_of_day() - six).rem_euclid(half_day);
+        Duration::nanoseconds(remaining / SPEED)
     }
     pub fn time(&self) -> NaiveTime {
-        self.0
+        self.time
+    }
+    /// JSTの0時から数えたアストルティアの日。現実の一日にアストルティアの20日がある
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+    fn nanos_of_day(&self) -> i64 {
+        self.time.num_seconds_from_midnight() as i64 * NANOS_PER_SEC + self.time.nanosecond() as i64
+    }
+    /// JSTの0時からのアストルティアの経過時間から求める
+    fn from_nanos(nanos: i64) -> AST {
+        let nanos = nanos.rem_euclid(NANOS_PER_DAY * SPEED);
+        let in_day = nanos % NANOS_PER_DAY;
+        let time = NaiveTime::from_num_seconds_from_midnight_opt(
+            (in_day / NANOS_PER_SEC) as u32,
+            (in_day % NANOS_PER_SEC) as u32,
+        ).unwrap();
+        AST { time, day: (nanos / NANOS_PER_DAY) as u32 }
+    }
+}
+
+/// 現実の時間が経過した後のアストルティア時刻。JSTの0時をまたぐとdayは0に戻る
+impl Add<Duration> for AST {
+    type Output = AST;
+
+    fn add(self, rhs: Duration) -> AST {
+        // 現実の一日はアストルティアのちょうど20日なので、一日未満の端数だけを足せばよい
+        // Durationの全体はi64のナノ秒に収まらないので、i128で端数を求める
+        let nanos = rhs.num_seconds() as i128 * NANOS_PER_SEC as i128 + rhs.subsec_nanos() as i128;
+        let nanos = nanos.rem_euclid(NANOS_PER_DAY as i128) as i64;
+        AST::from_nanos(self.day as i64 * NANOS_PER_DAY + self.nanos_of_day() + nanos * SPEED)
     }
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize)]
 pub enum State {
     Day,
     Night,
@@ -38,6 +88,17 @@ impl State {
     }
 }
 
+impl Serialize for AST {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("AST", 4)?;
+        s.serialize_field("time", &self.time.format("%H:%M:%S").to_string())?;
+        s.serialize_field("state", &self.state())?;
+        s.serialize_field("state_change_in", &self.state_change_in())?;
+        s.serialize_field("day", &self.day)?;
+        s.end()
+    }
+}
+
 impl std::fmt::Display for State {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -47,33 +108,86 @@ impl std::fmt::Display for State {
    }
 }
 
-pub fn now() -> AST {
-    from_datetime(Utc::now()).unwrap()
+pub fn now(clock: &impl Clock) -> AST {
+    from_datetime(clock.now()).unwrap()
 }
 
 
-pub fn from_timestamp(sec_from_epoch: i64) -> Option<AST> {
-    DateTime::from_timestamp(sec_from_epoch, 0).and_then(from_datetime)
+pub fn from_timestamp(sec_from_epoch: i64) -> error::Result<AST> {
+    DateTime::from_timestamp(sec_from_epoch, 0)
+        .ok_or(Error::TimestampOutOfRange(sec_from_epoch))
+        .and_then(from_datetime)
 }
 
-pub fn from_timestamp_millis(millis_from_epoch: i64) -> Option<AST> {
-    DateTime::from_timestamp_millis(millis_from_epoch).and_then(from_datetime)
+pub fn from_timestamp_millis(millis_from_epoch: i64) -> error::Result<AST> {
+    DateTime::from_timestamp_millis(millis_from_epoch)
+        .ok_or(Error::TimestampOutOfRange(millis_from_epoch))
+        .and_then(from_datetime)
 }
 
-pub fn from_datetime(dt: DateTime<Utc>) -> Option<AST> {
-    from_naive_utc(dt.naive_utc())
+pub fn from_datetime(dt: DateTime<Utc>) -> error::Result<AST> {
+    from_naive_utc(dt.naive_utc()).ok_or(Error::TimestampOutOfRange(dt.timestamp()))
 }
 
 
 fn from_naive_utc(nt: NaiveDateTime) -> Option<AST> {
     FixedOffset::east_opt(9 * 3600).and_then(|offset| {
         let jst = offset.from_utc_datetime(&nt);
-        offset.with_ymd_and_hms(jst.year(), jst.month(), jst.day(), 0, 0, 0).single().map(|jst0h| {
-            let duration = jst - jst0h;
-            let ast = jst0h + duration * 20;
-            ast.time()
+        offset.with_ymd_and_hms(jst.year(), jst.month(), jst.day(), 0, 0, 0).single().and_then(|jst0h| {
+            (jst - jst0h).num_nanoseconds()
+        })
+    }).map(|elapsed| AST::from_nanos(elapsed * SPEED))
+}
+
+/// アストルティアの一日は現実の72分。JSTの0時からアストルティア時刻の0時が始まる
+const AST_DAY_MINUTES: i64 = 72;
+
+/// 指定時刻以降に、アストルティア時刻がtimeになる現実の時刻を順に列挙する。指定時刻ちょうども含む
+/// 表せる時刻の終わりで列挙も終わる
+pub fn occurrences(dt: DateTime<Utc>, time: NaiveTime) -> error::Result<impl Iterator<Item = DateTime<Utc>>> {
+    let nanos = time.num_seconds_from_midnight() as i64 * NANOS_PER_SEC + time.nanosecond() as i64;
+    let first = FixedOffset::east_opt(9 * 3600).and_then(|offset| {
+        let jst = dt.with_timezone(&offset);
+        offset.with_ymd_and_hms(jst.year(), jst.month(), jst.day(), 0, 0, 0).single()
+    }).and_then(|jst0h| jst0h.to_utc().checked_add_signed(Duration::nanoseconds(nanos / SPEED)))
+        .ok_or(Error::TimestampOutOfRange(dt.timestamp()))?;
+
+    // 現実の一日はアストルティアのちょうど20日なので、JSTの日付をまたいでも72分間隔で続く
+    Ok((0..).map_while(move |day| Duration::try_minutes(AST_DAY_MINUTES * day).and_then(|d| first.checked_add_signed(d)))
+        .skip_while(move |t| *t < dt))
+}
+
+/// 指定時刻以降で、最初にアストルティア時刻がtimeになる現実の時刻
+pub fn next_occurrence(dt: DateTime<Utc>, time: NaiveTime) -> error::Result<DateTime<Utc>> {
+    occurrences(dt, time)?.next().ok_or(Error::TimestampOutOfRange(dt.timestamp()))
+}
+
+/// 朝と夜の切り替わり。atからstateになる
+#[derive(PartialEq, Eq, Debug, Serialize)]
+#[serde(bound(serialize = ""))]
+pub struct Transition<Tz: TimeZone> {
+    pub at: DateTime<Tz>,
+    pub state: State,
+}
+
+/// 指定時刻以降の朝と夜の切り替わりを順に列挙する。切り替わりは現実の36分ごと
+/// 表せる時刻の終わりで列挙も終わる
+pub fn transitions<Tz: TimeZone>(dt: DateTime<Utc>, tz: Tz) -> error::Result<impl Iterator<Item = Transition<Tz>>> {
+    let morning = next_occurrence(dt, NaiveTime::from_hms_opt(6, 0, 0).unwrap());
+    let evening = next_occurrence(dt, NaiveTime::from_hms_opt(18, 0, 0).unwrap());
+    let (first, state) = match (morning, evening) {
+        (Ok(morning), Ok(evening)) if evening < morning => (evening, State::Night),
+        (Ok(morning), _) => (morning, State::Day),
+        (Err(_), evening) => (evening?, State::Night),
+    };
+
+    Ok((0..).map_while(move |i| {
+        let at = Duration::try_minutes(AST_DAY_MINUTES / 2 * i).and_then(|d| first.checked_add_signed(d))?;
+        Some(Transition {
+            at: at.with_timezone(&tz),
+            state: if i % 2 == 0 { state } else { state.change() },
         })
-    }).map(AST)
+    }))
 }
 
 
@@ -81,6 +195,7 @@ fn from_naive_utc(nt: NaiveDateTime) -> Option<AST> {
 mod tests {
     use chrono::prelude::*;
     use chrono_tz;
+    use crate::clock::{Clock, FixedClock, OffsetClock};
     #[test]
     fn test_from_datetime_jst1() {
         let dt = chrono::FixedOffset::east_opt(9 * 3600).unwrap().with_ymd_and_hms(2018, 5, 31, 7, 3, 15).single().unwrap();
@@ -133,49 +248,49 @@ mod tests {
     #[test]
     fn test_calc_minutes_to_next0() {
         let t = chrono::NaiveTime::from_hms_milli_opt(6, 0, 1, 0).unwrap();
-        let s = super::AST(t);
+        let s = super::AST { time: t, day: 0 };
         assert_eq!(s.state().change().to_string(), "夜");
         assert_eq!(s.state_change_in(), 36);
     }
     #[test]
     fn test_calc_minutes_to_next1() {
         let t = chrono::NaiveTime::from_hms_milli_opt(12, 45, 30, 0).unwrap();
-        let s = super::AST(t);
+        let s = super::AST { time: t, day: 0 };
         assert_eq!(s.state().change().to_string(), "夜");
         assert_eq!(s.state_change_in(), 16);
     }
     #[test]
     fn test_calc_minutes_to_next2() {
         let t = chrono::NaiveTime::from_hms_milli_opt(5, 45, 30, 1).unwrap();
-        let s = super::AST(t);
+        let s = super::AST { time: t, day: 0 };
         assert_eq!(s.state().change().to_string(), "朝");
         assert_eq!(s.state_change_in(), 1);
     }
     #[test]
     fn test_calc_minutes_to_next3() {
         let t = chrono::NaiveTime::from_hms_milli_opt(23, 45, 30, 1).unwrap();
-        let s = super::AST(t);
+        let s = super::AST { time: t, day: 0 };
         assert_eq!(s.state().change().to_string(), "朝");
         assert_eq!(s.state_change_in(), 19);
     }
     #[test]
     fn test_calc_minutes_to_next4() {
         let t = chrono::NaiveTime::from_hms_milli_opt(0, 0, 0, 0).unwrap();
-        let s = super::AST(t);
+        let s = super::AST { time: t, day: 0 };
         assert_eq!(s.state().change().to_string(), "朝");
         assert_eq!(s.state_change_in(), 18);
     }
     #[test]
     fn test_calc_minutes_to_next5() {
         let t = chrono::NaiveTime::from_hms_milli_opt(6, 0, 0, 0).unwrap();
-        let s = super::AST(t);
+        let s = super::AST { time: t, day: 0 };
         assert_eq!(s.state().change().to_string(), "夜");
         assert_eq!(s.state_change_in(), 36);
     }
     #[test]
     fn test_calc_minutes_to_next6() {
         let t = chrono::NaiveTime::from_hms_milli_opt(18, 0, 0, 0).unwrap();
-        let s = super::AST(t);
+        let s = super::AST { time: t, day: 0 };
         assert_eq!(s.state().change().to_string(), "朝");
         assert_eq!(s.state_change_in(), 36);
     }
@@ -188,19 +303,17 @@ mod tests {
     }
     #[test]
     fn test_same_from_timestamp_and_utc_now() {
-        let utc = chrono::Utc::now();
-        // ミリ秒を含めない時刻に変換
-        let utc = chrono::Utc.with_ymd_and_hms(utc.year(), utc.month(), utc.day(), utc.hour(), utc.minute(), utc.second()).single().unwrap();
+        let clock = FixedClock(chrono::Utc.with_ymd_and_hms(2022, 7, 2, 4, 20, 54).unwrap());
+        let utc = clock.now();
         let epoch = utc.timestamp();
         let ast1 = super::from_timestamp(epoch).unwrap();
-        let ast2 = super::from_datetime(utc).unwrap();
+        let ast2 = super::now(&clock);
         assert_eq!(ast1, ast2)
     }
     #[test]
     fn test_same_from_timestamp_and_local_now() {
-        let l = chrono::Local::now();
-        // ミリ秒を含めない時刻に変換
-        let l = chrono::Local.with_ymd_and_hms(l.year(), l.month(), l.day(), l.hour(), l.minute(), l.second()).single().unwrap();
+        let clock = FixedClock(chrono::Utc.with_ymd_and_hms(2022, 7, 2, 4, 20, 54).unwrap());
+        let l = clock.now().with_timezone(&chrono::Local);
         let e = l.timestamp();
         let ast1 = super::from_timestamp(e).unwrap();
         let ast2 = super::from_datetime(l.to_utc()).unwrap();
@@ -208,18 +321,191 @@ mod tests {
     }
     #[test]
     fn test_same_utc_now_and_local_now() {
-        let l = chrono::Local::now();
-        let l = chrono::Local.with_ymd_and_hms(l.year(), l.month(), l.day(), l.hour(), l.minute(), l.second()).single().unwrap();
-        let u = chrono::Utc::now();
-        let u = chrono::Utc.with_ymd_and_hms(u.year(), u.month(), u.day(), u.hour(), u.minute(), u.second()).single().unwrap();
+        let clock = FixedClock(chrono::Utc.with_ymd_and_hms(2022, 7, 2, 4, 20, 54).unwrap());
+        let l = clock.now().with_timezone(&chrono::Local);
         let ast1 = super::from_datetime(l.to_utc()).unwrap();
-        let ast2 = super::from_datetime(u).unwrap();
+        let ast2 = super::now(&clock);
         assert_eq!(ast1, ast2)
     }
     #[test]
+    fn test_now_with_offset_clock() {
+        // 2022/07/02 13:20:54 JSTはアストルティア時刻の2:58
+        let base = FixedClock(chrono::Utc.with_ymd_and_hms(2022, 7, 2, 4, 0, 0).unwrap());
+        let clock = OffsetClock { clock: base, offset: chrono::Duration::seconds(20 * 60 + 54) };
+        let ast = super::now(&clock);
+        assert_eq!(ast.time().hour(), 2);
+        assert_eq!(ast.time().minute(), 58);
+    }
+    #[test]
+    fn test_from_timestamp_out_of_range() {
+        assert_eq!(super::from_timestamp(i64::MAX), Err(crate::error::Error::TimestampOutOfRange(i64::MAX)));
+        assert!(super::from_timestamp_millis(i64::MIN).is_err());
+    }
+    #[test]
     fn test_same_from_timestamp_and_timestamp_millis() {
         let ast1 = super::from_timestamp_millis(1678671352000).unwrap();
         let ast2 = super::from_timestamp(1678671352).unwrap();
         assert_eq!(ast1, ast2)
     }
+    #[test]
+    fn test_next_occurrence() {
+        // JST 7:03:15はアストルティア時刻の21:05。18:00はJSTの0:54から72分ごと
+        let tz = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        let dt = tz.with_ymd_and_hms(2018, 5, 31, 7, 3, 15).single().unwrap();
+        let time = chrono::NaiveTime::from_hms_opt(18, 0, 0).unwrap();
+        let next = super::next_occurrence(dt.to_utc(), time).unwrap();
+        assert_eq!(next, tz.with_ymd_and_hms(2018, 5, 31, 8, 6, 0).single().unwrap());
+        assert_eq!(super::from_datetime(next).unwrap().time(), time);
+    }
+    #[test]
+    fn test_next_occurrence_includes_now() {
+        let dt = chrono_tz::Asia::Tokyo.with_ymd_and_hms(2018, 6, 1, 0, 0, 0).single().unwrap().to_utc();
+        let midnight = chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+        assert_eq!(super::next_occurrence(dt, midnight), Ok(dt));
+    }
+    #[test]
+    fn test_occurrences_at_end_of_range() {
+        // 表せる時刻の終わりではpanicせずに列挙が終わる
+        let end = chrono::DateTime::<chrono::Utc>::MAX_UTC;
+        let midnight = chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+        assert!(super::occurrences(end - chrono::Duration::hours(12), midnight).unwrap().count() <= 10);
+        assert!(super::next_occurrence(end, midnight).is_err());
+        assert!(super::transitions(end - chrono::Duration::hours(12), chrono::Utc).unwrap().count() <= 20);
+        assert!(super::transitions(end, chrono::Utc).is_err());
+    }
+    #[test]
+    fn test_occurrences_across_jst_midnight() {
+        // JST 23:40以降のアストルティア時刻0:00は、JSTの0:00から72分ごと
+        let tz = chrono_tz::Asia::Tokyo;
+        let dt = tz.with_ymd_and_hms(2018, 5, 31, 23, 40, 0).single().unwrap();
+        let midnight = chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+        let list: Vec<_> = super::occurrences(dt.to_utc(), midnight).unwrap().take(3).collect();
+        assert_eq!(list[0], tz.with_ymd_and_hms(2018, 6, 1, 0, 0, 0).single().unwrap());
+        assert_eq!(list[1], tz.with_ymd_and_hms(2018, 6, 1, 1, 12, 0).single().unwrap());
+        assert_eq!(list[2], tz.with_ymd_and_hms(2018, 6, 1, 2, 24, 0).single().unwrap());
+    }
+    #[test]
+    fn test_occurrences_sub_second() {
+        // アストルティア時刻の1秒は現実の0.05秒
+        let dt = chrono::Utc.with_ymd_and_hms(2018, 6, 13, 1, 22, 30).single().unwrap();
+        let time = chrono::NaiveTime::from_hms_opt(15, 30, 1).unwrap();
+        let next = super::next_occurrence(dt, time).unwrap();
+        assert_eq!(next - dt, chrono::Duration::milliseconds(50));
+    }
+    #[test]
+    fn test_transitions() {
+        // アストルティア時刻21:05の次は6:00に朝、18:00に夜
+        let tz = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        let dt = tz.with_ymd_and_hms(2018, 5, 31, 7, 3, 15).single().unwrap();
+        let list: Vec<_> = super::transitions(dt.to_utc(), tz).unwrap().take(3).collect();
+        assert_eq!(list[0].at, tz.with_ymd_and_hms(2018, 5, 31, 7, 30, 0).single().unwrap());
+        assert_eq!(list[0].state, super::State::Day);
+        assert_eq!(list[1].at, tz.with_ymd_and_hms(2018, 5, 31, 8, 6, 0).single().unwrap());
+        assert_eq!(list[1].state, super::State::Night);
+        assert_eq!(list[2].at, tz.with_ymd_and_hms(2018, 5, 31, 8, 42, 0).single().unwrap());
+        assert_eq!(list[2].state, super::State::Day);
+        for t in list {
+            assert_eq!(super::from_datetime(t.at.to_utc()).unwrap().state(), t.state);
+        }
+    }
+    #[test]
+    fn test_transitions_across_jst_midnight() {
+        // JSTの0時にアストルティア時刻も0時に戻るが、切り替わりの36分間隔は日付をまたいでも変わらない
+        let tz = chrono_tz::Asia::Tokyo;
+        let dt = tz.with_ymd_and_hms(2018, 5, 31, 23, 30, 0).single().unwrap();
+        let list: Vec<_> = super::transitions(dt.to_utc(), tz).unwrap().take(2).collect();
+        assert_eq!(list[0].at, tz.with_ymd_and_hms(2018, 5, 31, 23, 42, 0).single().unwrap());
+        assert_eq!(list[0].state, super::State::Night);
+        assert_eq!(list[1].at, tz.with_ymd_and_hms(2018, 6, 1, 0, 18, 0).single().unwrap());
+        assert_eq!(list[1].state, super::State::Day);
+    }
+    fn ast(h: u32, m: u32, s: u32, nano: u32) -> super::AST {
+        super::AST { time: chrono::NaiveTime::from_hms_nano_opt(h, m, s, nano).unwrap(), day: 0 }
+    }
+    #[test]
+    fn test_until_state_change_boundaries() {
+        use chrono::Duration;
+        // 境界の直前は残り現実0.05秒、境界ちょうどは次の切り替わりまで36分
+        assert_eq!(ast(5, 59, 59, 0).until_state_change(), Duration::milliseconds(50));
+        assert_eq!(ast(5, 59, 59, 0).state(), super::State::Night);
+        assert_eq!(ast(6, 0, 0, 0).until_state_change(), Duration::minutes(36));
+        assert_eq!(ast(6, 0, 0, 0).state(), super::State::Day);
+        assert_eq!(ast(6, 0, 0, 20).until_state_change(), Duration::minutes(36) - Duration::nanoseconds(1));
+        assert_eq!(ast(17, 59, 59, 999_999_980).until_state_change(), Duration::nanoseconds(1));
+        assert_eq!(ast(17, 59, 59, 999_999_980).state(), super::State::Day);
+        assert_eq!(ast(18, 0, 0, 0).until_state_change(), Duration::minutes(36));
+        assert_eq!(ast(18, 0, 0, 0).state(), super::State::Night);
+        assert_eq!(ast(23, 59, 59, 0).until_state_change(), Duration::minutes(18) + Duration::milliseconds(50));
+        assert_eq!(ast(0, 0, 0, 0).until_state_change(), Duration::minutes(18));
+        assert_eq!(ast(12, 0, 0, 0).until_state_change(), Duration::minutes(18));
+    }
+    #[test]
+    fn test_state_change_in_rounding() {
+        // 残り29.95秒は切り捨て、30秒ちょうどは切り上げ
+        assert_eq!(ast(17, 50, 1, 0).state_change_in(), 0);
+        assert_eq!(ast(17, 50, 0, 0).state_change_in(), 1);
+        assert_eq!(ast(5, 59, 59, 0).state_change_in(), 0);
+    }
+    #[test]
+    fn test_day_counter() {
+        let tz = chrono_tz::Asia::Tokyo;
+        let at = |h, m, s| super::from_datetime(tz.with_ymd_and_hms(2018, 6, 1, h, m, s).single().unwrap().to_utc()).unwrap();
+        assert_eq!(at(0, 0, 0).day(), 0);
+        assert_eq!(at(1, 11, 59).day(), 0);
+        assert_eq!(at(1, 12, 0).day(), 1);
+        assert_eq!(at(1, 12, 0).time(), chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap());
+        assert_eq!(at(23, 59, 59).day(), 19);
+        assert_eq!(at(23, 59, 59).time(), chrono::NaiveTime::from_hms_opt(23, 59, 40).unwrap());
+    }
+    #[test]
+    fn test_add_duration() {
+        use chrono::Duration;
+        let t = ast(5, 0, 0, 0);
+        let after = t + Duration::minutes(3);
+        assert_eq!(after.time(), chrono::NaiveTime::from_hms_opt(6, 0, 0).unwrap());
+        assert_eq!(after.day(), 0);
+        // 72分でアストルティアの一日が進む
+        assert_eq!((t + Duration::minutes(72)).day(), 1);
+        assert_eq!((t + Duration::minutes(72)).time(), t.time());
+        // 現実の一日後は同じ時刻、同じ日に戻る
+        assert_eq!(t + Duration::days(1), t);
+        assert_eq!(t + Duration::days(-3), t);
+        // 負の時間はさかのぼり、JSTの0時より前は前日の19日目になる
+        let before = t + Duration::minutes(-16);
+        assert_eq!(before.time(), chrono::NaiveTime::from_hms_opt(23, 40, 0).unwrap());
+        assert_eq!(before.day(), 19);
+    }
+    #[test]
+    fn test_add_long_duration() {
+        use chrono::Duration;
+        let t = ast(5, 0, 0, 0);
+        // 日数がi32に、全体がi64のナノ秒に収まらなくても端数だけが進む
+        assert_eq!(t + Duration::days(1 << 31), t);
+        assert_eq!((t + (Duration::days(1 << 31) + Duration::minutes(3))).time(), chrono::NaiveTime::from_hms_opt(6, 0, 0).unwrap());
+        assert_eq!((t + (Duration::days(-(1 << 35)) - Duration::minutes(16))).time(), chrono::NaiveTime::from_hms_opt(23, 40, 0).unwrap());
+        let _ = t + Duration::milliseconds(i64::MAX);
+        let _ = t + Duration::milliseconds(-i64::MAX);
+    }
+    #[test]
+    fn test_add_duration_matches_from_datetime() {
+        // 一日分の各時刻で、足し算した結果と現実の時刻から求めた結果が一致する
+        let base = chrono::Utc.with_ymd_and_hms(2018, 6, 1, 14, 30, 0).single().unwrap();
+        let ast = super::from_datetime(base).unwrap();
+        for sec in (0..2 * 86_400).step_by(7) {
+            let d = chrono::Duration::seconds(sec);
+            assert_eq!(ast + d, super::from_datetime(base + d).unwrap(), "{} seconds", sec);
+        }
+    }
+    #[test]
+    fn test_until_state_change_matches_transitions() {
+        // 切り替わりまでの時間を足すと、ちょうど次の切り替わりになる
+        let base = chrono::Utc.with_ymd_and_hms(2018, 6, 1, 14, 0, 0).single().unwrap();
+        for sec in (0..86_400).step_by(13) {
+            let dt = base + chrono::Duration::seconds(sec);
+            let ast = super::from_datetime(dt).unwrap();
+            let next = super::transitions(dt + chrono::Duration::nanoseconds(1), chrono::Utc).unwrap().next().unwrap();
+            assert_eq!(dt + ast.until_state_change(), next.at.to_utc(), "{}", dt);
+            assert_eq!(next.state, ast.state().change());
+        }
+    }
 }
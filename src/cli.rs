@@ -0,0 +1,276 @@
+pub mod ast;
+pub mod defense_force;
+pub mod levels;
+pub mod now;
+pub mod panigarm;
+pub mod serve;
+pub mod tui;
+mod watch;
+pub mod webhook;
+
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use serde_json::Value;
+use std::process;
+use crate::clock::{self, OffsetClock, SystemClock};
+use crate::data;
+use crate::error::{self, Error};
+use crate::ical::{self, VEvent};
+use crate::saint_guardians::SaintGuardians;
+use crate::sinners::Sinners;
+use crate::zone::Zone;
+
+/// dq10コマンドのサブコマンド
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Show every rotation's current state and next change
+    Now(now::Args),
+    /// Show the Astoltia standard time
+    Ast(ast::Args),
+    /// Show the current defense force troop, or its schedule
+    Defense(defense_force::Args),
+    /// Show the Panigarm boss cycle
+    Panigarm(panigarm::Args),
+    /// Show the Sinners in the Abyss levels
+    Sinners(levels::Args),
+    /// Show the Saint Guardians levels
+    Guardians(levels::Args),
+    /// Show a full-screen dashboard of every rotation
+    Tui(tui::Args),
+    /// Keep posting rotation changes to Discord or Slack webhooks
+    Webhook(webhook::Args),
+    /// Serve every rotation as JSON over HTTP
+    Serve(serve::Args),
+}
+
+pub fn run(command: Option<Command>, format: Format, tz: Zone) {
+    setup();
+    match command {
+        None => now::run(now::Args {}, format, tz),
+        Some(Command::Now(args)) => now::run(args, format, tz),
+        Some(Command::Ast(args)) => ast::run(args, format, tz),
+        Some(Command::Defense(args)) => defense_force::run(args, format, tz),
+        Some(Command::Panigarm(args)) => panigarm::run(args, format, tz),
+        Some(Command::Sinners(args)) => levels::run(&Sinners, args, format, tz),
+        Some(Command::Guardians(args)) => levels::run(&SaintGuardians, args, format, tz),
+        Some(Command::Tui(args)) => tui::run(args, format, tz),
+        // お知らせの時刻はアストルティアの基準であるJSTで表すので、tzは使わない
+        Some(Command::Webhook(args)) => webhook::run(args, format),
+        Some(Command::Serve(args)) => serve::run(args, format, tz),
+    }
+}
+
+// 単独のコマンドとして実行する場合の引数。各コマンドの引数に--formatを加える
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Standalone<A: clap::Args> {
+    #[command(flatten)]
+    pub args: A,
+
+    /// output format
+    #[arg(short='f', long, value_enum, default_value_t)]
+    pub format: Format,
+
+    /// time zone to show and read date times in, such as Asia/Tokyo. "local" for the system time zone
+    #[arg(long, value_name = "TZ", default_value = "local")]
+    pub tz: Zone,
+}
+
+impl<A: clap::Args> Standalone<A> {
+    /// 引数を解釈し、ローテーションデータとDQ10_NOWを読み込む
+    pub fn init() -> Self {
+        let cli = Self::parse();
+        setup();
+        cli
+    }
+}
+
+/// データファイルやDQ10_NOWの誤りを、最初に使う場所でのpanicではなくエラーとして報告する
+fn setup() {
+    if let Err(e) = data::try_get().and(clock::global()) {
+        exit_with(e)
+    }
+}
+
+/// DQ10_NOWを反映した時計
+fn clock() -> &'static OffsetClock<SystemClock> {
+    clock::global().unwrap_or_else(|e| exit_with(e))
+}
+
+/// エラーを表示し、エラーの種類ごとの終了コードで終了する
+fn exit_with(e: Error) -> ! {
+    eprintln!("error: {}", e);
+    process::exit(e.exit_code())
+}
+
+/// 出力形式
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// human readable text
+    #[default]
+    Text,
+    /// JSON
+    Json,
+    /// CSV with a header row. nested fields are joined with "."
+    Csv,
+    /// iCalendar (RFC 5545)
+    Ics,
+}
+
+/// JSONまたはCSVで出力する
+fn print_data<T: Serialize>(format: Format, data: &T) {
+    let value = serde_json::to_value(data).unwrap();
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&value).unwrap()),
+        Format::Csv => print!("{}", to_csv(&value)),
+        _ => unsupported(format),
+    }
+}
+
+fn unsupported(format: Format) -> ! {
+    let name = format.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+    eprintln!("{} format is not supported by this command", name);
+    process::exit(1)
+}
+
+/// 配列は要素ごとに、それ以外は一行のCSVにする
+fn to_csv(value: &Value) -> String {
+    let rows: Vec<Vec<(String, String)>> = match value {
+        Value::Array(items) => items.iter().map(flatten).collect(),
+        v => vec![flatten(v)],
+    };
+
+    let mut header: Vec<&str> = Vec::new();
+    for (key, _) in rows.iter().flatten() {
+        if !header.contains(&key.as_str()) {
+            header.push(key);
+        }
+    }
+
+    let mut csv = header.iter().map(|h| quote(h)).collect::<Vec<_>>().join(",") + "\n";
+    for row in &rows {
+        let fields: Vec<String> = header.iter().map(|h| {
+            row.iter().find(|(key, _)| key == h).map(|(_, v)| quote(v)).unwrap_or_default()
+        }).collect();
+        csv += &(fields.join(",") + "\n");
+    }
+    csv
+}
+
+fn flatten(value: &Value) -> Vec<(String, String)> {
+    fn walk(prefix: &str, value: &Value, out: &mut Vec<(String, String)>) {
+        let key = |k: &str| if prefix.is_empty() { k.to_string() } else { format!("{}.{}", prefix, k) };
+        match value {
+            Value::Object(map) => map.iter().for_each(|(k, v)| walk(&key(k), v, out)),
+            Value::Array(items) => items.iter().enumerate().for_each(|(i, v)| walk(&key(&i.to_string()), v, out)),
+            Value::String(s) => out.push((prefix.to_string(), s.clone())),
+            Value::Null => out.push((prefix.to_string(), String::new())),
+            v => out.push((prefix.to_string(), v.to_string())),
+        }
+    }
+    let mut out = Vec::new();
+    walk("", value, &mut out);
+    out
+}
+
+fn quote(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn print_ics(name: &str, events: &[VEvent]) {
+    print!("{}", ical::to_ics(name, events, clock::now()));
+}
+
+/// "YYYY-MM-DD HH:MM"形式の文字列をtzの時刻として解釈する。省略時は現在時刻
+fn datetime_or_now(s: Option<&str>, tz: Zone) -> DateTime<Zone> {
+    match s {
+        None => clock::now().with_timezone(&tz),
+        Some(dt_str) => parse_as_local_datetime(dt_str, tz).unwrap_or_else(|e| exit_with(e)),
+    }
+}
+
+fn parse_as_local_datetime(s: &str, tz: Zone) -> error::Result<DateTime<Zone>>  {
+    match NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M") {
+        Ok(dt) => match tz.from_local_datetime(&dt).single() {
+            Some(dt) => Ok(dt),
+            None => Err(Error::AmbiguousLocalTime(s.to_string()))
+        },
+        Err(e) => Err(Error::invalid_date_time(s, format!("{}. use YYYY-MM-DD HH:MM", e)))
+    }
+}
+
+/// "5m", "30s", "1h"のような時間の長さを解釈する。単位を省略すると分
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "m"),
+    };
+    let n: i64 = number.parse().map_err(|_| format!("invalid duration {}", s))?;
+    let duration = match unit {
+        "s" => Duration::try_seconds(n),
+        "m" => Duration::try_minutes(n),
+        "h" => Duration::try_hours(n),
+        _ => return Err(format!("invalid duration {}. use s, m or h", s)),
+    };
+    duration.ok_or_else(|| format!("duration {} is too long", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_as_local_datetime() {
+        let tz = crate::zone::Zone::Named(chrono_tz::Asia::Tokyo);
+        let dt = super::parse_as_local_datetime("2025-12-10 06:00", tz).unwrap();
+        assert_eq!(dt, chrono::Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap());
+        // ニューヨークの夏時間の始まりで、2:30は存在しない
+        let e = super::parse_as_local_datetime("2025-03-09 02:30", "America/New_York".parse().unwrap()).unwrap_err();
+        assert_eq!(e.exit_code(), 5);
+        let e = super::parse_as_local_datetime("2025/12/10", tz).unwrap_err();
+        assert_eq!(e.exit_code(), 3);
+        assert!(e.to_string().contains("YYYY-MM-DD HH:MM"));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        use chrono::Duration;
+        assert_eq!(super::parse_duration("5m"), Ok(Duration::minutes(5)));
+        assert_eq!(super::parse_duration("30s"), Ok(Duration::seconds(30)));
+        assert_eq!(super::parse_duration("1h"), Ok(Duration::hours(1)));
+        assert_eq!(super::parse_duration("10"), Ok(Duration::minutes(10)));
+        assert!(super::parse_duration("5d").is_err());
+        assert!(super::parse_duration("m").is_err());
+        assert!(super::parse_duration("9999999999999999h").is_err());
+    }
+
+    #[test]
+    fn test_to_csv_array() {
+        let value = json!([
+            { "started_at": "2025-12-10T06:00:00+09:00", "troop": { "id": "GOLD", "name": "金神の遺宝兵団" } },
+            { "started_at": "2025-12-10T07:00:00+09:00", "troop": { "id": "GANG", "name": "紅爆の暴賊兵団" } }
+        ]);
+        assert_eq!(super::to_csv(&value), "started_at,troop.id,troop.name\n\
+            2025-12-10T06:00:00+09:00,GOLD,金神の遺宝兵団\n\
+            2025-12-10T07:00:00+09:00,GANG,紅爆の暴賊兵団\n");
+    }
+
+    #[test]
+    fn test_to_csv_object() {
+        let value = json!({ "time": "21:05:00", "state": "Night", "state_change_in": 19 });
+        assert_eq!(super::to_csv(&value), "time,state,state_change_in\n21:05:00,Night,19\n");
+    }
+
+    #[test]
+    fn test_to_csv_quote() {
+        let value = json!([{ "a": "x,y", "b": "say \"hi\"" }]);
+        assert_eq!(super::to_csv(&value), "a,b\n\"x,y\",\"say \"\"hi\"\"\"\n");
+    }
+}
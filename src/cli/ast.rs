@@ -0,0 +1,131 @@
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Timelike, Utc};
+use chrono_tz::Asia::Tokyo;
+use serde::Serialize;
+use super::Format;
+use crate::ast;
+use crate::clock;
+use crate::zone::Zone;
+
+// --scheduleで一度に表示する上限。一年分
+const MAX_HOURS: i64 = 24 * 366;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// show when the Astoltia time next reaches the given time, in the format of "HH:MM"
+    #[arg(short='u', long, value_name = "HH:MM", value_parser = parse_time)]
+    until: Option<NaiveTime>,
+
+    /// number of occurrences to be displayed with --until
+    #[arg(short='l', long, default_value_t=1, requires="until")]
+    list: usize,
+
+    /// show the day/night transitions in the next hours as a table
+    #[arg(short='s', long)]
+    schedule: bool,
+
+    /// number of real hours covered by --schedule, up to 8784 (a year)
+    #[arg(long, default_value_t=6, requires="schedule", value_parser = clap::value_parser!(u32).range(1..=MAX_HOURS))]
+    hours: u32,
+
+    /// keep the Astoltia clock and a countdown to the next day/night change updated every second
+    #[arg(short='w', long)]
+    watch: bool,
+}
+
+/// アストルティア時刻が指定時刻になる現実の時刻
+#[derive(Serialize)]
+struct Arrival {
+    at: DateTime<Zone>,
+    /// 現在からの秒数
+    in_seconds: i64,
+}
+
+pub fn run(args: Args, format: Format, tz: Zone) {
+    if let Some(time) = args.until {
+        return run_until(time, args.list, format, tz);
+    }
+    if args.schedule {
+        return run_schedule(args.hours, format, tz);
+    }
+    if args.watch {
+        return run_watch(format);
+    }
+
+    let astortia_time = ast::now(super::clock());
+
+    if format != Format::Text {
+        return super::print_data(format, &astortia_time);
+    }
+
+    let naive_time = astortia_time.time();
+    println!("現在のアストルティア時刻は{: >02}時{: >02}分{: >02}秒", naive_time.hour(), naive_time.minute(), naive_time.second());
+
+    let remaining = astortia_time.until_state_change().num_seconds();
+    println!("{}分{:02}秒後に{}になります", remaining / 60, remaining % 60, astortia_time.state().change())
+}
+
+fn run_watch(format: Format) {
+    if format != Format::Text {
+        super::unsupported(format);
+    }
+    super::watch::watch(|now| {
+        let ast = match ast::from_datetime(now) {
+            Ok(ast) => ast,
+            Err(e) => return vec![e.to_string()],
+        };
+        vec![
+            format!("アストルティア時刻 {} {}", ast.time().format("%H:%M:%S"), ast.state()),
+            format!("{}後に{}になります", super::watch::countdown(ast.until_state_change()), ast.state().change()),
+        ]
+    });
+}
+
+fn run_until(time: NaiveTime, count: usize, format: Format, tz: Zone) {
+    let now = clock::now();
+    let arrivals: Vec<Arrival> = ast::occurrences(now, time).unwrap_or_else(|e| super::exit_with(e)).take(count)
+        .map(|at| Arrival { at: at.with_timezone(&tz), in_seconds: (at - now).num_seconds() })
+        .collect();
+
+    if format != Format::Text {
+        return super::print_data(format, &arrivals);
+    }
+
+    println!("アストルティア時刻の{}になる時刻", time.format("%H:%M"));
+    for arrival in arrivals {
+        println!("{} (約{}分後)", arrival.at.format("%m/%d %H:%M:%S"), (arrival.in_seconds + 30) / 60);
+    }
+}
+
+fn run_schedule(hours: u32, format: Format, tz: Zone) {
+    let now = clock::now();
+    let limit = Duration::try_hours(hours.into())
+        .and_then(|hours| now.checked_add_signed(hours))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC);
+    let transitions: Vec<ast::Transition<Zone>> = ast::transitions(now, tz)
+        .unwrap_or_else(|e| super::exit_with(e))
+        .take_while(|t| t.at < limit)
+        .collect();
+
+    if format != Format::Text {
+        return super::print_data(format, &transitions);
+    }
+
+    println!("時刻\t\t状態");
+    let mut date = now.with_timezone(&Tokyo).date_naive();
+    for transition in transitions {
+        // JSTの0時にアストルティア時刻も0時に戻る
+        let jst_date = transition.at.with_timezone(&Tokyo).date_naive();
+        if jst_date != date {
+            let midnight = Tokyo.from_local_datetime(&jst_date.and_hms_opt(0, 0, 0).unwrap()).unwrap();
+            println!("{}\tJST 0時 (アストルティア時刻 0時)", midnight.with_timezone(&tz).format("%m/%d %H:%M"));
+            date = jst_date;
+        }
+        println!("{}\t{}", transition.at.format("%m/%d %H:%M"), transition.state);
+    }
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M:%S"))
+        .map_err(|e| format!("invalid time format {}. {}.", s, e))
+}
@@ -0,0 +1,245 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use clap::{Subcommand, ValueEnum};
+use std::process;
+use super::Format;
+use crate::clock;
+use crate::zone::Zone;
+use crate::defense_force;
+use crate::defense_force::schedule::Window;
+use crate::ical;
+use crate::notify::{self, Bell, Notification, NotifySend, ShellCommand, Sink};
+
+#[derive(clap::Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// keep the current troop and a countdown to the next one updated every second
+    #[arg(short='w', long)]
+    watch: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show the defense force schedule
+    Schedule(ScheduleArgs),
+    /// Keep running and notify before each window of a troop
+    Notify(NotifyArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ScheduleArgs {
+    /// starting date time of events to be displayed, in the format of "YYYY-MM-DD HH:MM"
+    #[arg(short='d', long)]
+    datetime: Option<String>,
+
+    /// number of events to be displayed [default: 24, or 5 with --find]
+    #[arg(short='c', long)]
+    count: Option<usize>,
+
+    /// show the next windows of the troop instead, by name, alias or a part of the name
+    #[arg(long, value_name = "TROOP")]
+    find: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct NotifyArgs {
+    /// troop to be notified, by name, alias or a part of the name
+    #[arg(short='t', long)]
+    troop: String,
+
+    /// how long before the window to notify, e.g. "5m", "30s", "1h"
+    #[arg(short='b', long, default_value = "5m", value_parser = super::parse_duration)]
+    before: Duration,
+
+    /// where to send notifications
+    #[arg(short='s', long, value_enum, default_value_t)]
+    sink: SinkKind,
+
+    /// shell command run by the command sink. DQ10_TITLE, DQ10_MESSAGE, DQ10_START and DQ10_END are set
+    #[arg(long, required_if_eq("sink", "command"))]
+    command: Option<String>,
+}
+
+/// 通知の送り先
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SinkKind {
+    /// ring the terminal bell and print the message
+    #[default]
+    Bell,
+    /// desktop notification via notify-send
+    NotifySend,
+    /// run the shell command given by --command
+    Command,
+}
+
+pub fn run(args: Args, format: Format, tz: Zone) {
+    match args.command {
+        None if args.watch => run_watch(format, tz),
+        None => run_state(format, tz),
+        Some(Command::Schedule(args)) => run_schedule(args, format, tz),
+        Some(Command::Notify(args)) => run_notify(args, format, tz),
+    }
+}
+
+pub fn run_state(format: Format, tz: Zone) {
+    let state = defense_force::state::get_current_state(super::clock(), tz).unwrap_or_else(|e| super::exit_with(e));
+    if format != Format::Text {
+        return super::print_data(format, &state);
+    }
+    println!("現在の敵は{}です", state.troop.colorized_name());
+    println!("{}分後に{}に変わります", state.next_in, state.next_troop.colorized_name());
+}
+
+fn run_watch(format: Format, tz: Zone) {
+    if format != Format::Text {
+        super::unsupported(format);
+    }
+    super::watch::watch(|now| {
+        let state = match defense_force::state::get_state(now, tz) {
+            Ok(state) => state,
+            Err(e) => return vec![e.to_string()],
+        };
+        vec![
+            format!("現在の敵は{}です", state.troop.colorized_name()),
+            format!("{}後 ({})に{}に変わります",
+                super::watch::countdown(state.changed_at.to_utc() - now),
+                state.changed_at.format("%H:%M"),
+                state.next_troop.colorized_name()),
+        ]
+    });
+}
+
+pub fn run_schedule(args: ScheduleArgs, format: Format, tz: Zone) {
+    let dt = super::datetime_or_now(args.datetime.as_deref(), tz).to_utc();
+
+    if let Some(query) = args.find {
+        return run_find(&query, dt, args.count.unwrap_or(5), format, tz);
+    }
+
+    let count = args.count.unwrap_or(24);
+
+    let windows = defense_force::schedule::get_windows_in(dt, count, tz)
+        .unwrap_or_else(|e| super::exit_with(e));
+
+    match format {
+        Format::Text => print_windows(&windows, dt),
+        Format::Ics => super::print_ics("防衛軍", &ical::from_windows(&windows)),
+        _ => super::print_data(format, &windows),
+    }
+}
+
+fn print_windows(windows: &[Window<Zone>], dt: DateTime<Utc>) {
+    for window in windows {
+        println!("{} - {} {} {}",
+            window.start.format("%m/%d %H:%M"),
+            window.end.format("%H:%M"),
+            length(window, dt),
+            window.troop.colorized_name());
+    }
+}
+
+/// 出現中の期間は残り時間、それ以外は期間の長さ
+fn length<Tz: TimeZone>(window: &Window<Tz>, dt: DateTime<Utc>) -> String {
+    if window.is_current_at(dt) {
+        format!("(残り{})", hours_and_minutes(window.remaining))
+    } else {
+        format!("({})", hours_and_minutes(window.duration))
+    }
+}
+
+fn hours_and_minutes(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}分", m),
+        (h, 0) => format!("{}時間", h),
+        (h, m) => format!("{}時間{}分", h, m),
+    }
+}
+
+fn run_find(query: &str, dt: DateTime<Utc>, count: usize, format: Format, tz: Zone) {
+    let troop = defense_force::troop::find_troop(query).unwrap_or_else(|e| super::exit_with(e));
+
+    let windows = defense_force::schedule::find_windows(troop, dt, count, tz);
+
+    match format {
+        Format::Text => print_windows(&windows, dt),
+        Format::Ics => super::print_ics(troop.name(), &ical::from_windows(&windows)),
+        _ => super::print_data(format, &windows),
+    }
+}
+
+fn run_notify(args: NotifyArgs, format: Format, tz: Zone) {
+    if format != Format::Text {
+        super::unsupported(format);
+    }
+
+    let troop = defense_force::troop::find_troop(&args.troop).unwrap_or_else(|e| super::exit_with(e));
+    let sink: Box<dyn Sink> = match args.sink {
+        SinkKind::Bell => Box::new(Bell),
+        SinkKind::NotifySend => Box::new(NotifySend),
+        SinkKind::Command => Box::new(ShellCommand(args.command.unwrap_or_default())),
+    };
+
+    let mut after = clock::now();
+    loop {
+        // 出現中の期間は通知しないので、次に始まる期間を探す
+        let Some(window) = defense_force::schedule::find_windows(troop, after, 2, tz).into_iter().find(|w| w.start > after) else {
+            eprintln!("{} does not appear within a year", troop.name());
+            process::exit(1)
+        };
+        let start = window.start.to_utc();
+
+        if let Some(alert_at) = notify::alert_at(start, args.before, clock::now()) {
+            super::watch::sleep_until(alert_at);
+        }
+
+        let minutes = (start - clock::now()).num_seconds().max(0).div_euclid(60);
+        let notification = Notification {
+            title: troop.name().to_string(),
+            message: format!("{}が{}分後 ({}) に始まります", troop.name(), minutes, window.start.format("%H:%M")),
+            start,
+            end: window.end.to_utc(),
+        };
+        if let Err(e) = sink.send(&notification) {
+            eprintln!("{}", e);
+        }
+
+        after = start;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use clap::Parser;
+    use crate::defense_force::schedule;
+
+    #[test]
+    fn test_watch_conflicts_with_subcommand() {
+        #[derive(clap::Parser)]
+        struct Cli {
+            #[command(flatten)]
+            args: super::Args,
+        }
+        assert!(Cli::try_parse_from(["defense", "--watch"]).is_ok());
+        assert!(Cli::try_parse_from(["defense", "schedule"]).is_ok());
+        assert!(Cli::try_parse_from(["defense", "--watch", "schedule"]).is_err());
+    }
+
+    #[test]
+    fn test_hours_and_minutes() {
+        assert_eq!(super::hours_and_minutes(Duration::minutes(56)), "56分");
+        assert_eq!(super::hours_and_minutes(Duration::hours(2)), "2時間");
+        assert_eq!(super::hours_and_minutes(Duration::minutes(90)), "1時間30分");
+    }
+
+    #[test]
+    fn test_length() {
+        let dt = Utc.with_ymd_and_hms(2025, 12, 9, 21, 30, 0).unwrap();
+        let windows = schedule::get_windows_in(dt, 2, Utc).unwrap();
+        assert_eq!(super::length(&windows[0], dt), "(残り30分)");
+        assert_eq!(super::length(&windows[1], dt), "(1時間)");
+    }
+}
@@ -0,0 +1,58 @@
+use std::iter;
+use chrono::{DateTime, Duration, Utc};
+use super::Format;
+use crate::ical;
+use crate::levels::{self, Levels, LevelsContent};
+use crate::zone::Zone;
+
+/// アビスの咎人と聖守護者に共通の引数
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// date time to look up levels, in the format of "YYYY-MM-DD HH:MM"
+    #[arg(short='d', long)]
+    datetime: Option<String>,
+
+    /// number of days to be displayed
+    #[arg(short='c', long, default_value_t=1)]
+    count: usize,
+}
+
+pub fn run<C: LevelsContent>(content: &C, args: Args, format: Format, tz: Zone) {
+    let dt = super::datetime_or_now(args.datetime.as_deref(), tz).to_utc();
+
+    match format {
+        Format::Ics => super::print_ics(C::TITLE, &ical::from_levels(content, dt, args.count)),
+        Format::Json | Format::Csv => super::print_data(format, &collect_levels::<C>(dt, args.count, tz)),
+        Format::Text if args.count > 1 => print_levels_table::<C>(dt, args.count, tz),
+        Format::Text => print_levels(levels::get_levels::<C, _>(dt, tz).unwrap_or_else(|e| super::exit_with(e))),
+    }
+}
+
+/// 指定日時から一日ずつのレベル
+fn collect_levels<C: LevelsContent>(dt: DateTime<Utc>, days: usize, tz: Zone) -> Vec<Levels<Zone>> {
+    let first = levels::get_levels::<C, _>(dt, tz).unwrap_or_else(|e| super::exit_with(e));
+    iter::successors(Some(first), |levels| {
+        levels::get_levels::<C, _>((levels.term.to + Duration::seconds(1)).to_utc(), tz).ok()
+    }).take(days).collect()
+}
+
+fn print_levels(levels: Levels<Zone>) {
+    let from = levels.term.from.format("%Y-%m-%d %H:%M:%S");
+    let to = levels.term.to.format("%Y-%m-%d %H:%M:%S");
+
+    println!("{} から {} まで", from, to);
+    for (boss, level) in levels.levels {
+        println!("{}\t{}", boss.name(), level);
+    }
+}
+
+/// 日替わりのレベルを一日一行の表で表示する。最初の日のレベルが得られなければエラーで終了する
+fn print_levels_table<C: LevelsContent>(dt: DateTime<Utc>, days: usize, tz: Zone) {
+    let table = collect_levels::<C>(dt, days, tz);
+    let header: Vec<&str> = table[0].levels.iter().map(|(boss, _)| boss.name()).collect();
+    println!("開始\t\t{}", header.join("\t"));
+    for levels in table {
+        let row: Vec<String> = levels.levels.iter().map(|(_, level)| level.to_string()).collect();
+        println!("{}\t{}", levels.term.from.format("%m/%d %H:%M"), row.join("\t"));
+    }
+}
@@ -0,0 +1,101 @@
+use chrono::{DateTime, TimeZone, Timelike, Utc};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use super::Format;
+use crate::ast;
+use crate::clock;
+use crate::defense_force::DefenseForce;
+use crate::levels::{self, Boss, Level};
+use crate::panigarm::Panigarm;
+use crate::rotation::Rotation;
+use crate::saint_guardians::SaintGuardians;
+use crate::sinners::Sinners;
+use crate::zone::Zone;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {}
+
+/// 全コンテンツの現在の状態と次の切り替わりを一画面に表示する
+pub fn run(_args: Args, format: Format, tz: Zone) {
+    let now = clock::now();
+
+    if format != Format::Text {
+        let data = json!({
+            "ast": ast::from_datetime(now).ok(),
+            "defense_force": status(&DefenseForce, now, &tz, |troop| troop),
+            "panigarm": status(&Panigarm, now, &tz, |boss| boss),
+            "sinners": status(&Sinners, now, &tz, levels_map),
+            "saint_guardians": status(&SaintGuardians, now, &tz, levels_map),
+        });
+        return super::print_data(format, &data);
+    }
+
+    if let Ok(ast) = ast::from_datetime(now) {
+        let t = ast.time();
+        println!("アストルティア時刻  {:02}:{:02} {} (約{}分で{})", t.hour(), t.minute(), ast.state(), ast.state_change_in(), ast.state().change());
+    }
+
+    print_rotation("防衛軍", &DefenseForce, now, tz, |troop| troop.colorized_name());
+    print_rotation("パニガルム", &Panigarm, now, tz, |boss| super::panigarm::bold(boss.name));
+    print_rotation("アビスの咎人", &Sinners, now, tz, |levels| levels::format_levels(levels));
+    print_rotation("聖守護者", &SaintGuardians, now, tz, |levels| levels::format_levels(levels));
+}
+
+/// 現在の区間と次の区間
+#[derive(Serialize)]
+#[serde(bound(serialize = "T: Serialize"))]
+struct Status<T, Tz: TimeZone> {
+    current: Period<T, Tz>,
+    next: Option<Period<T, Tz>>,
+}
+
+/// 区間の値と、指定されたタイムゾーンでの開始・終了時刻
+#[derive(Serialize)]
+#[serde(bound(serialize = "T: Serialize"))]
+struct Period<T, Tz: TimeZone> {
+    value: T,
+    start: DateTime<Tz>,
+    end: DateTime<Tz>,
+}
+
+fn status<R: Rotation, T, Tz: TimeZone>(rotation: &R, now: DateTime<Utc>, tz: &Tz, map: impl Fn(R::Item) -> T) -> Option<Status<T, Tz>> {
+    let mut upcoming = rotation.upcoming(now).map(|o| Period {
+        value: map(o.value),
+        start: o.start.with_timezone(tz),
+        end: o.end.with_timezone(tz),
+    });
+    let current = upcoming.next()?;
+    Some(Status { current, next: upcoming.next() })
+}
+
+fn levels_map(levels: Vec<(Boss, Level)>) -> Map<String, Value> {
+    levels.iter().map(|(boss, level)| (boss.name().to_string(), json!(level))).collect()
+}
+
+fn print_rotation<R: Rotation>(label: &str, rotation: &R, now: DateTime<Utc>, tz: Zone, format: impl Fn(&R::Item) -> String) {
+    let Some(current) = rotation.current_at(now) else {
+        println!("{}  no data", label);
+        return;
+    };
+    println!("{}  {}", label, format(&current.value));
+    if let Some(next) = rotation.current_at(current.end) {
+        println!("    {} から {}", current.end.with_timezone(&tz).format("%m/%d %H:%M"), format(&next.value));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use crate::defense_force::DefenseForce;
+
+    #[test]
+    fn test_status_in_time_zone() {
+        let now = Utc.with_ymd_and_hms(2025, 12, 11, 9, 4, 0).unwrap();
+        let status = super::status(&DefenseForce, now, &chrono_tz::Asia::Tokyo, |troop| troop).unwrap();
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["current"]["start"], "2025-12-11T18:00:00+09:00");
+        assert_eq!(json["current"]["end"], "2025-12-11T19:00:00+09:00");
+        assert_eq!(json["next"]["start"], "2025-12-11T19:00:00+09:00");
+    }
+}
@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use super::Format;
+use crate::ical::{self, VEvent};
+use crate::panigarm;
+use crate::zone::Zone;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// starting date time, in the format of "YYYY-MM-DD HH:MM"
+    #[arg(short='d', long)]
+    datetime: Option<String>,
+
+    /// show the next windows of the boss instead, by name, alias or a part of the name
+    #[arg(short='b', long)]
+    boss: Option<String>,
+
+    /// number of windows to be displayed with --boss
+    #[arg(short='c', long, default_value_t=5, requires="boss")]
+    count: usize,
+}
+
+pub fn run(args: Args, format: Format, tz: Zone) {
+    let dt = super::datetime_or_now(args.datetime.as_deref(), tz).to_utc();
+
+    if let Some(query) = args.boss {
+        return run_find(&query, dt, args.count, format, tz);
+    }
+
+    let cycle = panigarm::get_cycle(dt, tz).unwrap_or_else(|e| super::exit_with(e));
+
+    match format {
+        Format::Text => {
+            for event in cycle {
+                println!("{} {}", event.started_at.format("%m/%d %H:%M"), bold(event.boss.name));
+            }
+        }
+        Format::Ics => super::print_ics("パニガルム", &ical::from_cycle(&cycle)),
+        _ => super::print_data(format, &cycle),
+    }
+}
+
+fn run_find(query: &str, dt: DateTime<Utc>, count: usize, format: Format, tz: Zone) {
+    let boss = panigarm::find_boss(query).unwrap_or_else(|e| super::exit_with(e));
+
+    let windows = panigarm::find_windows(boss, dt, count, tz);
+
+    match format {
+        Format::Text => {
+            for window in windows {
+                println!("{} - {} {}",
+                    window.start.format("%m/%d %H:%M"),
+                    window.end.format("%m/%d %H:%M"),
+                    bold(window.boss.name));
+            }
+        }
+        Format::Ics => {
+            let events: Vec<VEvent> = windows.iter()
+                .map(|w| VEvent::new("panigarm", w.boss.name.to_string(), w.start.to_utc(), w.end.to_utc()))
+                .collect();
+            super::print_ics(boss.name, &events)
+        }
+        _ => super::print_data(format, &windows),
+    }
+}
+
+pub(crate) fn bold(s: &str) -> String {
+    format!("\x1b[1m{}\x1b[0m", s)
+}
@@ -0,0 +1,27 @@
+use super::Format;
+use crate::server;
+use crate::zone::Zone;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// port to listen on
+    #[arg(short='p', long, default_value_t=8080)]
+    port: u16,
+
+    /// address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+}
+
+/// JSONを返すHTTPサーバーを起動する
+pub fn run(args: Args, format: Format, tz: Zone) {
+    if format != Format::Text {
+        super::unsupported(format);
+    }
+
+    let addr = format!("{}:{}", args.host, args.port);
+    eprintln!("listening on http://{}", addr);
+    if let Err(e) = server::serve(&addr, tz) {
+        super::exit_with(e)
+    }
+}
@@ -0,0 +1,241 @@
+use std::io;
+use std::time::Duration as StdDuration;
+use chrono::{DateTime, Duration, Utc};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use super::Format;
+use crate::ast;
+use crate::clock;
+use crate::defense_force::schedule;
+use crate::defense_force::state;
+use crate::defense_force::troop::{ColorStyle, Troop};
+use crate::levels::{Boss, Level};
+use crate::panigarm;
+use crate::saint_guardians;
+use crate::sinners;
+use crate::zone::Zone;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {}
+
+/// 全コンテンツをまとめた画面を表示し続ける。qかCtrl-Cで終了する
+pub fn run(_args: Args, format: Format, tz: Zone) {
+    if format != Format::Text {
+        super::unsupported(format);
+    }
+
+    let mut terminal = ratatui::init();
+    let result = Dashboard { offset_hours: 0, tz }.run(&mut terminal);
+    ratatui::restore();
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    }
+}
+
+/// 現在時刻からどれだけ先を表示しているか。時計と現在の兵団は常に現在時刻で表示する
+struct Dashboard {
+    offset_hours: i64,
+    tz: Zone,
+}
+
+impl Dashboard {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            let now = clock::now();
+            terminal.draw(|frame| self.draw(frame, now))?;
+
+            // 秒の変わり目まで入力を待つ
+            let timeout = (Duration::seconds(1) - Duration::nanoseconds(now.timestamp_subsec_nanos() as i64))
+                .to_std()
+                .unwrap_or(StdDuration::ZERO);
+            if !event::poll(timeout)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.scroll(1),
+                KeyCode::Up | KeyCode::Char('k') => self.scroll(-1),
+                KeyCode::PageDown | KeyCode::Char('l') => self.scroll(24),
+                KeyCode::PageUp | KeyCode::Char('h') => self.scroll(-24),
+                KeyCode::Home | KeyCode::Char('0') => self.offset_hours = 0,
+                _ => {}
+            }
+        }
+    }
+
+    /// 現在より前には戻らない
+    fn scroll(&mut self, hours: i64) {
+        self.offset_hours = (self.offset_hours + hours).max(0);
+    }
+
+    fn draw(&self, frame: &mut Frame, now: DateTime<Utc>) {
+        let view = now + Duration::hours(self.offset_hours);
+
+        let [header, current, timelines, levels, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(4),
+            Constraint::Min(10),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ]).areas(frame.area());
+
+        let title = if self.offset_hours == 0 {
+            format!("dq10tools  {}", now.with_timezone(&self.tz).format("%Y-%m-%d %H:%M:%S"))
+        } else {
+            format!("dq10tools  {}  (+{}時間: {}を表示中)",
+                now.with_timezone(&self.tz).format("%Y-%m-%d %H:%M:%S"),
+                self.offset_hours,
+                view.with_timezone(&self.tz).format("%m/%d %H:%M"))
+        };
+        frame.render_widget(Paragraph::new(title).style(Style::new().add_modifier(Modifier::BOLD)), header);
+
+        let [clock, troop] = Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(current);
+        frame.render_widget(ast_clock(now), clock);
+        frame.render_widget(current_troop(now, self.tz), troop);
+
+        let [schedule, cycle] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(timelines);
+        self.draw_schedule(frame, schedule, view);
+        frame.render_widget(panigarm_cycle(now, view, self.tz), cycle);
+
+        let [sinners, guardians] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(levels);
+        frame.render_widget(levels_widget("アビスの咎人", sinners::get_levels(view, self.tz).ok().map(|l| l.levels)), sinners);
+        frame.render_widget(levels_widget("聖守護者", saint_guardians::get_levels(view, self.tz).ok().map(|l| l.levels)), guardians);
+
+        frame.render_widget(Paragraph::new("↑↓/jk: 1時間  PgUp/PgDn/hl: 1日  Home/0: 現在  q: 終了").style(Style::new().fg(Color::DarkGray)), help);
+    }
+
+    /// 表示位置から24時間分の防衛軍。現在の兵団は反転して表示する
+    fn draw_schedule(&self, frame: &mut Frame, area: Rect, view: DateTime<Utc>) {
+        let events = schedule::get_schedule_in(view, 24, self.tz).unwrap_or_default();
+        let lines: Vec<Line> = events.iter().enumerate().map(|(i, event)| {
+            let mut spans = vec![Span::raw(format!("{} ", event.started_at.format("%m/%d %H:%M")))];
+            spans.extend(troop_spans(&event.troop));
+            let line = Line::from(spans);
+            if self.offset_hours == 0 && i == 0 {
+                line.style(Style::new().add_modifier(Modifier::REVERSED))
+            } else {
+                line
+            }
+        }).collect();
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title("防衛軍スケジュール")), area);
+    }
+}
+
+fn ast_clock(now: DateTime<Utc>) -> Paragraph<'static> {
+    let lines = match ast::from_datetime(now) {
+        Ok(ast) => vec![
+            Line::from(format!("{} {}", ast.time().format("%H:%M:%S"), ast.state())),
+            Line::from(format!("{}後に{}", super::watch::countdown(ast.until_state_change()), ast.state().change())),
+        ],
+        Err(e) => vec![Line::from(e.to_string())],
+    };
+    Paragraph::new(lines).block(Block::bordered().title("アストルティア時刻"))
+}
+
+fn current_troop(now: DateTime<Utc>, tz: Zone) -> Paragraph<'static> {
+    let lines = match state::get_state(now, tz) {
+        Ok(state) => {
+            let mut next = vec![Span::raw(format!("{}後 ({}) ", super::watch::countdown(state.changed_at.to_utc() - now), state.changed_at.format("%H:%M")))];
+            next.extend(troop_spans(&state.next_troop));
+            vec![Line::from(troop_spans(&state.troop)), Line::from(next)]
+        }
+        Err(e) => vec![Line::from(e.to_string())],
+    };
+    Paragraph::new(lines).block(Block::bordered().title("防衛軍"))
+}
+
+/// 表示位置から一周期分のパニガルム。現在のボスは太字
+fn panigarm_cycle(now: DateTime<Utc>, view: DateTime<Utc>, tz: Zone) -> Paragraph<'static> {
+    let current = panigarm::get_event(now, tz).ok().map(|e| e.started_at);
+    let lines: Vec<Line> = panigarm::get_cycle(view, tz).unwrap_or_default().iter().map(|event| {
+        let line = Line::from(format!("{} {}", event.started_at.format("%m/%d %H:%M"), event.boss.name));
+        if Some(event.started_at) == current {
+            line.style(Style::new().add_modifier(Modifier::BOLD))
+        } else {
+            line
+        }
+    }).collect();
+    Paragraph::new(lines).block(Block::bordered().title("パニガルム"))
+}
+
+fn levels_widget(title: &'static str, levels: Option<Vec<(Boss, Level)>>) -> Paragraph<'static> {
+    let lines: Vec<Line> = match levels {
+        Some(levels) => levels.iter().map(|(boss, level)| Line::from(format!("{} {}", boss.name(), level))).collect(),
+        None => vec![Line::from("no data")],
+    };
+    Paragraph::new(lines).block(Block::bordered().title(title))
+}
+
+/// 兵団名を兵団の色で表示する。colorized_nameと同じ色にする
+fn troop_spans(troop: &Troop) -> Vec<Span<'static>> {
+    let name = troop.name();
+    match troop.color() {
+        ColorStyle::Standard(code) => vec![Span::styled(name, standard_style(code))],
+        ColorStyle::Extended(code) => vec![Span::styled(name, Style::new().fg(Color::Indexed(code as u8)))],
+        ColorStyle::Rainbow => name.chars().enumerate().map(|(i, ch)| {
+            Span::styled(ch.to_string(), standard_style((i as u32 + 6) % 7 + 31))
+        }).collect(),
+    }
+}
+
+/// SGRのコードをスタイルにする。30から37は前景色、1は太字
+fn standard_style(code: u32) -> Style {
+    match code {
+        1 => Style::new().add_modifier(Modifier::BOLD),
+        30..=37 => Style::new().fg(Color::Indexed((code - 30) as u8)),
+        _ => Style::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use ratatui::backend::TestBackend;
+    use ratatui::style::{Color, Modifier};
+    use ratatui::Terminal;
+    use crate::defense_force::troop;
+    use crate::zone::Zone;
+
+    #[test]
+    fn test_troop_spans() {
+        let gold = troop::find_troop("GOLD").unwrap();
+        assert_eq!(super::troop_spans(&gold)[0].style.fg, Some(Color::Indexed(220)));
+
+        let all = troop::find_troop("ALL").unwrap();
+        assert!(super::troop_spans(&all)[0].style.add_modifier.contains(Modifier::BOLD));
+
+        let slime = troop::find_troop("SLIME").unwrap();
+        let spans = super::troop_spans(&slime);
+        assert_eq!(spans.len(), slime.name().chars().count());
+        assert_eq!(spans[0].style.fg, Some(Color::Indexed(7)));
+        assert_eq!(spans[1].style.fg, Some(Color::Indexed(1)));
+    }
+
+    #[test]
+    fn test_draw() {
+        let now = chrono::Utc.with_ymd_and_hms(2025, 12, 10, 3, 30, 0).unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        let dashboard = super::Dashboard { offset_hours: 0, tz: Zone::Named(chrono_tz::Asia::Tokyo) };
+        terminal.draw(|frame| dashboard.draw(frame, now)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        // 全角文字の後ろのセルは空白になるので除いて比べる
+        let text: String = buffer.content().iter().map(|cell| cell.symbol()).filter(|s| *s != " ").collect();
+        for label in ["アストルティア時刻", "防衛軍スケジュール", "パニガルム", "アビスの咎人", "聖守護者"] {
+            assert!(text.contains(label), "{}", label);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Duration, Utc};
+use std::io::{self, Write};
+use std::sync::mpsc;
+use std::thread;
+use crate::clock;
+
+/// 画面を一秒ごとに描き直す。Ctrl-Cで終了する
+///
+/// 毎回の描画は秒の変わり目に行う。防衛軍や朝夜の切り替わりはちょうど秒の変わり目にあるので、
+/// 切り替わった瞬間に新しい状態が表示される
+pub(crate) fn watch(mut render: impl FnMut(DateTime<Utc>) -> Vec<String>) {
+    let (tx, rx) = mpsc::channel();
+    if let Err(e) = ctrlc::set_handler(move || { let _ = tx.send(()); }) {
+        eprintln!("{}", e);
+        return;
+    }
+
+    let mut stdout = io::stdout();
+    // カーソルを隠す
+    print!("\x1b[?25l");
+
+    let mut drawn = 0;
+    loop {
+        let now = clock::now();
+        let lines = render(now);
+
+        // 前回の描画の先頭に戻り、一行ずつ上書きする。画面全体は消さないのでちらつかない
+        let mut frame = String::new();
+        if drawn > 0 {
+            frame += &format!("\x1b[{}A", drawn);
+        }
+        for line in &lines {
+            frame += &format!("\r\x1b[2K{}\n", line);
+        }
+        // 前回より行が減った場合は残りを消す
+        frame += "\x1b[J";
+        print!("{}", frame);
+        let _ = stdout.flush();
+        drawn = lines.len();
+
+        if rx.recv_timeout(until_next_second(now)).is_ok() {
+            break;
+        }
+    }
+
+    print!("\x1b[?25h");
+    let _ = stdout.flush();
+}
+
+fn until_next_second(now: DateTime<Utc>) -> std::time::Duration {
+    let next = now.timestamp() + 1;
+    let next = DateTime::from_timestamp(next, 0).unwrap();
+    (next - now).to_std().unwrap_or_default()
+}
+
+// 寝ている間に時計が変わっても大きく遅れないように、長く待つときは区切って時刻を確かめ直す
+const MAX_SLEEP_SECONDS: i64 = 60;
+
+/// 指定時刻まで待つ
+pub(crate) fn sleep_until(at: DateTime<Utc>) {
+    loop {
+        let wait = at - clock::now();
+        if wait <= Duration::zero() {
+            return;
+        }
+        thread::sleep(wait.min(Duration::seconds(MAX_SLEEP_SECONDS)).to_std().unwrap_or_default());
+    }
+}
+
+/// 残り時間を"H:MM:SS"の形式にする
+pub(crate) fn countdown(remaining: Duration) -> String {
+    let seconds = remaining.num_seconds().max(0);
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_until_next_second() {
+        let now = Utc.with_ymd_and_hms(2025, 12, 10, 6, 0, 0).unwrap() + Duration::milliseconds(250);
+        assert_eq!(super::until_next_second(now), std::time::Duration::from_millis(750));
+    }
+
+    #[test]
+    fn test_countdown() {
+        assert_eq!(super::countdown(Duration::seconds(3725)), "1:02:05");
+        assert_eq!(super::countdown(Duration::seconds(59)), "0:00:59");
+        assert_eq!(super::countdown(Duration::seconds(-1)), "0:00:00");
+    }
+}
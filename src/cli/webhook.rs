@@ -0,0 +1,90 @@
+use std::process;
+use std::time::Duration as StdDuration;
+use chrono::{DateTime, Utc};
+use super::Format;
+use crate::clock;
+use crate::notify::{Notification, Sink};
+use crate::webhook::{self, DryRun, Flavor, Retry, Retrying, Webhook};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Discord webhook URL. can be given more than once
+    #[arg(long, value_name = "URL")]
+    discord: Vec<String>,
+
+    /// Slack incoming webhook URL. can be given more than once
+    #[arg(long, value_name = "URL")]
+    slack: Vec<String>,
+
+    /// print the payloads instead of posting them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// post the current state of every rotation once and exit
+    #[arg(long)]
+    once: bool,
+
+    /// number of attempts for each post
+    #[arg(long, default_value_t=4)]
+    attempts: u32,
+
+    /// seconds to wait before the first retry. doubled for each retry
+    #[arg(long, default_value_t=2)]
+    backoff: u64,
+}
+
+/// 各コンテンツが切り替わるたびにWebhookへお知らせを送り続ける
+pub fn run(args: Args, format: Format) {
+    if format != Format::Text {
+        super::unsupported(format);
+    }
+
+    let retry = Retry { attempts: args.attempts.max(1), backoff: StdDuration::from_secs(args.backoff) };
+    let webhooks = args.discord.iter().map(|url| (url, Flavor::Discord))
+        .chain(args.slack.iter().map(|url| (url, Flavor::Slack)))
+        .map(|(url, flavor)| Webhook { retry, on_retry: report_retry, ..Webhook::new(url, flavor) });
+    let sinks: Vec<Box<dyn Sink>> = if args.dry_run {
+        webhooks.map(|w| Box::new(DryRun(w)) as Box<dyn Sink>).collect()
+    } else {
+        webhooks.map(|w| Box::new(w) as Box<dyn Sink>).collect()
+    };
+    if sinks.is_empty() {
+        eprintln!("no webhook. use --discord or --slack");
+        process::exit(1)
+    }
+
+    let mut after = clock::now();
+    if args.once {
+        return post(&sinks, after, webhook::status_at(after));
+    }
+
+    while let Some(next) = webhook::next_change(after) {
+        super::watch::sleep_until(next);
+        post(&sinks, next, webhook::announcements_at(next));
+        after = next;
+    }
+    eprintln!("no more changes");
+    process::exit(1)
+}
+
+fn report_retry(retrying: &Retrying) {
+    eprintln!("{}. retrying in {}s", retrying.error, retrying.wait.as_secs_f64());
+}
+
+fn post(sinks: &[Box<dyn Sink>], dt: DateTime<Utc>, lines: Vec<String>) {
+    if lines.is_empty() {
+        return;
+    }
+    let notification = Notification {
+        title: "dq10tools".to_string(),
+        message: lines.join("\n"),
+        start: dt,
+        end: webhook::next_change(dt).unwrap_or(dt),
+    };
+    // 一つのWebhookが失敗しても他には送る
+    for sink in sinks {
+        if let Err(e) = sink.send(&notification) {
+            eprintln!("{}", e);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::OnceLock;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use crate::error::{Error, Result};
+
+/// 現在時刻を上書きする環境変数。RFC 3339の日時か、Unix epochからのミリ秒
+pub const NOW_ENV: &str = "DQ10_NOW";
+
+/// 現在時刻を返すもの。get_current_*系の関数はこれを受け取る
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// システム時刻
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// つねに同じ時刻を返す
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// 元の時計からoffsetだけずれた時刻を返す。時刻は元の時計と同じように進む
+#[derive(Debug, Clone, Copy)]
+pub struct OffsetClock<C: Clock> {
+    pub clock: C,
+    pub offset: Duration,
+}
+
+impl<C: Clock> OffsetClock<C> {
+    /// 今この瞬間がstartになるようにずらす
+    pub fn starting_at(clock: C, start: DateTime<Utc>) -> Self {
+        let offset = start - clock.now();
+        OffsetClock { clock, offset }
+    }
+}
+
+impl<C: Clock> Clock for OffsetClock<C> {
+    fn now(&self) -> DateTime<Utc> {
+        self.clock.now() + self.offset
+    }
+}
+
+static GLOBAL: OnceLock<Result<OffsetClock<SystemClock>>> = OnceLock::new();
+
+/// DQ10_NOWを反映したプロセス全体の時計。DQ10_NOWの時刻から始まり、システム時刻と同じように進む
+/// 環境変数は最初に呼ばれたときに読む。未設定ならシステム時刻と同じ
+pub fn global() -> Result<&'static OffsetClock<SystemClock>> {
+    GLOBAL.get_or_init(|| from_env_value(std::env::var(NOW_ENV).ok().as_deref()))
+        .as_ref().map_err(Clone::clone)
+}
+
+/// プロセス全体の時計での現在時刻。DQ10_NOWが正しくなければシステム時刻
+pub fn now() -> DateTime<Utc> {
+    global().map_or_else(|_| Utc::now(), Clock::now)
+}
+
+/// RFC 3339の日時か、Unix epochからのミリ秒
+pub fn parse_time(s: &str) -> Result<DateTime<Utc>> {
+    if let Ok(millis) = s.parse::<i64>() {
+        return Utc.timestamp_millis_opt(millis).single().ok_or(Error::TimestampOutOfRange(millis));
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.to_utc())
+        .map_err(|e| Error::invalid_date_time(s, format!("{}. use RFC 3339 or epoch milliseconds", e)))
+}
+
+fn from_env_value(value: Option<&str>) -> Result<OffsetClock<SystemClock>> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(OffsetClock { clock: SystemClock, offset: Duration::zero() }),
+        Some(value) => Ok(OffsetClock::starting_at(SystemClock, parse_time(value)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use super::{Clock, FixedClock, OffsetClock};
+
+    #[test]
+    fn test_fixed_clock() {
+        let dt = Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap();
+        let clock = FixedClock(dt);
+        assert_eq!(clock.now(), dt);
+        assert_eq!(clock.now(), dt);
+    }
+
+    #[test]
+    fn test_offset_clock() {
+        let dt = Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap();
+        let clock = OffsetClock { clock: FixedClock(dt), offset: Duration::hours(-1) };
+        assert_eq!(clock.now(), dt - Duration::hours(1));
+        let clock = OffsetClock::starting_at(FixedClock(dt), dt + Duration::days(3));
+        assert_eq!(clock.offset, Duration::days(3));
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let dt = Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap();
+        assert_eq!(super::parse_time("2025-12-10T06:00:00+09:00"), Ok(dt));
+        assert_eq!(super::parse_time("2025-12-09T21:00:00Z"), Ok(dt));
+        assert_eq!(super::parse_time(&dt.timestamp_millis().to_string()), Ok(dt));
+        assert!(matches!(super::parse_time("2025-12-10 06:00"), Err(crate::error::Error::InvalidDateTime { .. })));
+        assert_eq!(super::parse_time(&i64::MAX.to_string()), Err(crate::error::Error::TimestampOutOfRange(i64::MAX)));
+    }
+
+    #[test]
+    fn test_from_env_value() {
+        let dt = Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap();
+        assert_eq!(super::from_env_value(None).unwrap().offset, Duration::zero());
+        assert_eq!(super::from_env_value(Some("")).unwrap().offset, Duration::zero());
+        // システム時刻から始まるので、数秒以内の誤差は許す
+        let now = super::from_env_value(Some("2025-12-10T06:00:00+09:00")).unwrap().now();
+        assert!((now - dt).num_seconds().abs() < 5);
+        let now = super::from_env_value(Some(&dt.timestamp_millis().to_string())).unwrap().now();
+        assert!((now - dt).num_seconds().abs() < 5);
+        assert!(super::from_env_value(Some("yesterday")).is_err());
+    }
+}
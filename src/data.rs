@@ -0,0 +1,439 @@
+use std::path::Path;
+use std::sync::OnceLock;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use serde::Deserialize;
+use crate::defense_force::troop::ColorStyle;
+use crate::error::{Error, Result};
+
+/// ローテーションデータの差し替えに使う環境変数。JSONファイルのパスを指定する
+pub const DATA_PATH_ENV: &str = "DQ10TOOLS_DATA";
+
+const DEFAULT_DATA: &str = include_str!("../data/rotations.json");
+
+static DATA: OnceLock<Result<Data>> = OnceLock::new();
+
+#[derive(Debug, Deserialize)]
+pub struct Data {
+    pub defense_force: DefenseForceData,
+    pub panigarm: PanigarmData,
+    pub sinners: LevelsData,
+    pub saint_guardians: LevelsData,
+}
+
+/// あるバージョンで有効なローテーション
+/// startから次のEraのstartまで、base_pointを起点に周期ごとにcycleの要素が順番に切り替わる
+#[derive(Debug, Deserialize)]
+pub struct Era<T> {
+    #[serde(default)]
+    pub version: Option<String>,
+    pub start: DateTime<FixedOffset>,
+    pub base_point: DateTime<FixedOffset>,
+    pub period_hours: i64,
+    pub cycle: Vec<T>,
+}
+
+impl<T> Era<T> {
+    pub fn start(&self) -> NaiveDateTime {
+        self.start.naive_utc()
+    }
+
+    pub fn base_point(&self) -> NaiveDateTime {
+        self.base_point.naive_utc()
+    }
+
+    pub fn period(&self) -> Duration {
+        Duration::hours(self.period_hours)
+    }
+
+    /// 指定時刻が起点から何周期目か
+    pub fn period_at(&self, dt: NaiveDateTime) -> i64 {
+        (dt - self.base_point()).num_seconds().div_euclid(self.period().num_seconds())
+    }
+
+    /// periodの周期の開始時刻。日時として表せなければNone
+    pub fn period_start(&self, period: i64) -> Option<NaiveDateTime> {
+        let elapsed = self.period_hours.checked_mul(period).and_then(Duration::try_hours)?;
+        self.base_point().checked_add_signed(elapsed)
+    }
+
+    pub fn entry(&self, period: i64) -> &T {
+        &self.cycle[period.rem_euclid(self.cycle.len() as i64) as usize]
+    }
+}
+
+/// 時系列順に並んだEraの一覧
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Eras<T>(pub Vec<Era<T>>);
+
+/// ある時刻を含む一周期分の区間。Eraの切り替わりで途中から始まる、または途中で終わることがある
+pub struct Slot<'a, T> {
+    pub era: &'a Era<T>,
+    pub period: i64,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl<T> Slot<'_, T> {
+    pub fn entry(&self) -> &T {
+        self.era.entry(self.period)
+    }
+}
+
+impl<T> Eras<T> {
+    /// 最初のEraの開始時刻。これより前のデータはない
+    pub fn first_start(&self) -> NaiveDateTime {
+        self.0[0].start()
+    }
+
+    pub fn era_at(&self, dt: NaiveDateTime) -> Option<&Era<T>> {
+        self.0.iter().rev().find(|era| era.start() <= dt)
+    }
+
+    fn next_start(&self, dt: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.0.iter().map(|era| era.start()).find(|start| *start > dt)
+    }
+
+    /// slot_atと同じ。最初のEraより前はBeforeBasePoint、区間の終わりが日時として表せなければTimestampOutOfRangeになる
+    pub fn try_slot_at(&self, dt: NaiveDateTime) -> Result<Slot<'_, T>> {
+        if dt < self.first_start() {
+            return Err(Error::BeforeBasePoint(Utc.from_utc_datetime(&self.first_start())));
+        }
+        self.slot_at(dt).ok_or(Error::TimestampOutOfRange(dt.and_utc().timestamp()))
+    }
+
+    /// 指定時刻を含む区間。最初のEraより前や、区間の終わりが日時として表せなければNone
+    pub fn slot_at(&self, dt: NaiveDateTime) -> Option<Slot<'_, T>> {
+        let era = self.era_at(dt)?;
+        let period = era.period_at(dt);
+        let start = era.period_start(period)?.max(era.start());
+        let end = era.period_start(period + 1)?;
+        let end = match self.next_start(dt) {
+            Some(next) if next < end => next,
+            _ => end,
+        };
+        Some(Slot { era, period, start, end })
+    }
+}
+
+/// 名前で探せるデータ。兵団やボス
+pub trait Named {
+    fn id(&self) -> &str;
+    fn name(&self) -> &str;
+    /// 検索に使う別名
+    fn aliases(&self) -> &[String];
+}
+
+/// 名前で探す。kindはエラーに使う"troop"や"boss"
+/// 正式名、ID、別名のいずれかに一致するもの、なければ正式名か別名の一部に一致するものを返す。大文字小文字は区別しない
+pub fn find_by_name<'a, T: Named>(items: &'a [T], kind: &'static str, query: &str) -> Result<&'a T> {
+    let original = query.trim();
+    let query = original.to_lowercase();
+    let names_of = |items: &[&T]| items.iter().map(|item| item.name().to_string()).collect();
+    let all: Vec<&T> = items.iter().collect();
+    if query.is_empty() {
+        return Err(Error::UnknownName { kind, query: original.to_string(), candidates: names_of(&all) });
+    }
+
+    let names = |item: &T| {
+        [item.name(), item.id()].into_iter().chain(item.aliases().iter().map(String::as_str)).map(str::to_lowercase).collect::<Vec<_>>()
+    };
+
+    if let Some(item) = items.iter().find(|item| names(item).contains(&query)) {
+        return Ok(item);
+    }
+
+    let candidates: Vec<&T> = items.iter()
+        .filter(|item| names(item).iter().any(|name| name.contains(&query)))
+        .collect();
+    match candidates[..] {
+        [item] => Ok(item),
+        [] => Err(Error::UnknownName { kind, query: original.to_string(), candidates: names_of(&all) }),
+        _ => Err(Error::AmbiguousName { kind, query: original.to_string(), candidates: names_of(&candidates) }),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TroopData {
+    pub id: String,
+    pub name: String,
+    pub color: ColorStyle,
+    /// 検索に使う別名
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl Named for TroopData {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DefenseForceData {
+    pub troops: Vec<TroopData>,
+    pub eras: Eras<String>,
+}
+
+impl DefenseForceData {
+    pub fn troop(&self, id: &str) -> Option<&TroopData> {
+        self.troops.iter().find(|t| t.id == id)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BossData {
+    pub id: String,
+    pub name: String,
+    /// 検索に使う別名
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl Named for BossData {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PanigarmData {
+    pub bosses: Vec<BossData>,
+    pub eras: Eras<String>,
+}
+
+impl PanigarmData {
+    pub fn boss(&self, id: &str) -> Option<&BossData> {
+        self.bosses.iter().find(|b| b.id == id)
+    }
+}
+
+/// 日替わりでボスのレベルが変わるコンテンツ。cycleの各要素はbossesと同じ順のレベル
+#[derive(Debug, Deserialize)]
+pub struct LevelsData {
+    pub bosses: Vec<BossData>,
+    pub eras: Eras<Vec<u32>>,
+}
+
+/// ローテーションデータを取得する
+/// 環境変数DQ10TOOLS_DATAが指定されていればそのファイルを、なければ組み込みのデータを使う
+pub fn try_get() -> Result<&'static Data> {
+    DATA.get_or_init(|| match std::env::var_os(DATA_PATH_ENV) {
+        Some(path) => load(&path),
+        None => parse(DEFAULT_DATA),
+    }).as_ref().map_err(Clone::clone)
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Data> {
+    let path = path.as_ref();
+    let s = std::fs::read_to_string(path).map_err(|e| Error::InvalidData(format!("{}: {}", path.display(), e)))?;
+    parse(&s).map_err(|e| match e {
+        Error::InvalidData(message) => Error::InvalidData(format!("{}: {}", path.display(), message)),
+        e => e,
+    })
+}
+
+pub fn parse(s: &str) -> Result<Data> {
+    let data: Data = serde_json::from_str(s).map_err(|e| Error::InvalidData(e.to_string()))?;
+    validate(&data).map_err(Error::InvalidData)?;
+    Ok(data)
+}
+
+fn validate(data: &Data) -> std::result::Result<(), String> {
+    let df = &data.defense_force;
+    validate_eras("defense_force", &df.eras)?;
+    // 防衛軍は1時間単位で切り替わるので、Eraも正時に切り替わる必要がある
+    let first_start = df.eras.first_start();
+    if df.eras.0.iter().any(|era| (era.start() - first_start).num_seconds() % 3600 != 0) {
+        return Err("defense_force: eras must start on the hour".to_string());
+    }
+    // 同じ兵団が続く時間をまとめるので、兵団が切り替わらない周期は終わりが見つからない
+    if df.eras.0.iter().any(|era| era.cycle.iter().all(|id| *id == era.cycle[0])) {
+        return Err("defense_force: cycle must contain at least two different troops".to_string());
+    }
+    for id in df.eras.0.iter().flat_map(|era| &era.cycle) {
+        if df.troop(id).is_none() {
+            return Err(format!("defense_force: unknown troop {}", id));
+        }
+    }
+
+    let panigarm = &data.panigarm;
+    validate_eras("panigarm", &panigarm.eras)?;
+    for id in panigarm.eras.0.iter().flat_map(|era| &era.cycle) {
+        if panigarm.boss(id).is_none() {
+            return Err(format!("panigarm: unknown boss {}", id));
+        }
+    }
+
+    validate_levels("sinners", &data.sinners)?;
+    validate_levels("saint_guardians", &data.saint_guardians)?;
+
+    Ok(())
+}
+
+fn validate_levels(name: &str, levels: &LevelsData) -> std::result::Result<(), String> {
+    validate_eras(name, &levels.eras)?;
+    if levels.eras.0.iter().flat_map(|era| &era.cycle).any(|l| l.len() != levels.bosses.len()) {
+        return Err(format!("{}: number of levels does not match number of bosses", name));
+    }
+    Ok(())
+}
+
+fn validate_eras<T>(name: &str, eras: &Eras<T>) -> std::result::Result<(), String> {
+    if eras.0.is_empty() {
+        return Err(format!("{}: no eras", name));
+    }
+    if eras.0.windows(2).any(|w| w[0].start >= w[1].start) {
+        return Err(format!("{}: eras must be sorted by start", name));
+    }
+    for era in &eras.0 {
+        if era.period_hours <= 0 || Duration::try_hours(era.period_hours).is_none() {
+            return Err(format!("{}: period_hours must be positive", name));
+        }
+        if era.cycle.is_empty() {
+            return Err(format!("{}: empty cycle", name));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::prelude::*;
+
+    #[test]
+    fn test_parse_default_data() {
+        let data = super::parse(super::DEFAULT_DATA).unwrap();
+        assert_eq!(data.defense_force.eras.0[0].cycle.len(), 30);
+        assert_eq!(data.defense_force.eras.first_start(), Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap().naive_utc());
+        assert_eq!(data.panigarm.eras.0[0].period_hours, 72);
+        assert_eq!(data.sinners.eras.0[0].entry(3), &vec![1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn test_parse_unknown_troop_is_error() {
+        let s = super::DEFAULT_DATA.replacen("\"GOLD\", \"GANG\"", "\"GOLD\", \"UNKNOWN\"", 1);
+        let err = super::parse(&s).unwrap_err();
+        assert!(matches!(err, crate::error::Error::InvalidData(_)));
+        assert!(err.to_string().contains("UNKNOWN"));
+    }
+
+    #[test]
+    fn test_parse_single_troop_cycle_is_error() {
+        let mut data: serde_json::Value = serde_json::from_str(super::DEFAULT_DATA).unwrap();
+        data["defense_force"]["eras"][0]["cycle"] = serde_json::json!(["GOLD", "GOLD", "GOLD"]);
+        let err = super::parse(&data.to_string()).unwrap_err();
+        assert!(err.to_string().contains("two different troops"));
+    }
+
+    #[test]
+    fn test_parse_renamed_and_added_level_bosses() {
+        // レベルのボスの名前はデータで変えられ、ボスを加えることもできる
+        let mut data: serde_json::Value = serde_json::from_str(super::DEFAULT_DATA).unwrap();
+        data["sinners"]["bosses"][0]["name"] = serde_json::json!("アウルモッド");
+        data["sinners"]["bosses"].as_array_mut().unwrap().push(serde_json::json!({ "id": "NEW", "name": "新しいボス" }));
+        data["sinners"]["eras"][0]["cycle"] = serde_json::json!([[2, 1, 2, 1, 3], [1, 2, 1, 2, 3]]);
+        let data = super::parse(&data.to_string()).unwrap();
+        let names: Vec<_> = data.sinners.bosses.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["アウルモッド", "狂禍のフラウソン", "悲愴のウィリーデ", "燦滅のノクゼリア", "新しいボス"]);
+    }
+
+    #[test]
+    fn test_parse_level_count_mismatch_is_error() {
+        let s = super::DEFAULT_DATA.replacen("[2, 1, 2, 1]", "[2, 1, 2]", 1);
+        assert!(super::parse(&s).is_err());
+    }
+
+    #[test]
+    fn test_earlier_era_resolves_past_dates() {
+        // 組み込みのデータより前の定義を先頭に加えると、その期間も計算できる
+        let earlier = r#"{ "version": "old", "start": "2024-01-01T06:00:00+09:00", "base_point": "2024-01-01T06:00:00+09:00", "period_hours": 1, "cycle": ["SLIME", "ALL"] },"#;
+        let s = super::DEFAULT_DATA.replacen("\"eras\": [", &format!("\"eras\": [{}", earlier), 1);
+        let data = super::parse(&s).unwrap();
+        let dt = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap().naive_utc();
+        let slot = data.defense_force.eras.slot_at(dt).unwrap();
+        assert_eq!(slot.era.version.as_deref(), Some("old"));
+        let dt = Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap().naive_utc();
+        assert_eq!(data.defense_force.eras.slot_at(dt).unwrap().era.version.as_deref(), Some("7.6"));
+    }
+
+    // 3時間周期のv1が、周期の途中の10:00からv2に切り替わる
+    fn eras() -> super::Eras<String> {
+        serde_json::from_str(r#"[
+            { "version": "1", "start": "2025-01-01T00:00:00Z", "base_point": "2025-01-01T00:00:00Z", "period_hours": 3, "cycle": ["A", "B"] },
+            { "version": "2", "start": "2025-01-01T10:00:00Z", "base_point": "2025-01-01T10:00:00Z", "period_hours": 3, "cycle": ["C", "D", "E"] }
+        ]"#).unwrap()
+    }
+
+    fn at(h: u32) -> NaiveDateTime {
+        Utc.with_ymd_and_hms(2025, 1, 1, h, 0, 0).unwrap().naive_utc()
+    }
+
+    #[test]
+    fn test_slot_before_first_era_is_none() {
+        let dt = Utc.with_ymd_and_hms(2024, 12, 31, 23, 59, 59).unwrap().naive_utc();
+        assert!(eras().slot_at(dt).is_none());
+    }
+
+    #[test]
+    fn test_slot_in_first_era() {
+        let eras = eras();
+        let slot = eras.slot_at(at(4)).unwrap();
+        assert_eq!(slot.era.version.as_deref(), Some("1"));
+        assert_eq!(slot.entry(), "B");
+        assert_eq!(slot.start, at(3));
+        assert_eq!(slot.end, at(6));
+    }
+
+    #[test]
+    fn test_slot_cut_by_next_era() {
+        // 9:00からの周期はv2の開始で10:00に打ち切られる
+        let eras = eras();
+        let slot = eras.slot_at(at(9)).unwrap();
+        assert_eq!(slot.entry(), "B");
+        assert_eq!(slot.start, at(9));
+        assert_eq!(slot.end, at(10));
+    }
+
+    #[test]
+    fn test_slot_in_next_era() {
+        let eras = eras();
+        let slot = eras.slot_at(at(10)).unwrap();
+        assert_eq!(slot.era.version.as_deref(), Some("2"));
+        assert_eq!(slot.entry(), "C");
+        assert_eq!(slot.start, at(10));
+        assert_eq!(slot.end, at(13));
+
+        let slot = eras.slot_at(at(17)).unwrap();
+        assert_eq!(slot.entry(), "E");
+        assert_eq!(slot.start, at(16));
+
+        let slot = eras.slot_at(at(20)).unwrap();
+        assert_eq!(slot.entry(), "C");
+        assert_eq!(slot.start, at(19));
+    }
+
+    #[test]
+    fn test_slot_at_end_of_range_is_none() {
+        let eras = eras();
+        assert!(eras.slot_at(NaiveDateTime::MAX).is_none());
+        assert!(matches!(eras.try_slot_at(NaiveDateTime::MAX), Err(crate::error::Error::TimestampOutOfRange(_))));
+        assert!(eras.0[0].period_start(i64::MAX).is_none());
+    }
+}
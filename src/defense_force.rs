@@ -1,3 +1,35 @@
 pub mod troop;
 pub mod state;
 pub mod schedule;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use crate::rotation::{Occurrence, Rotation};
+use troop::{calc_period, get_base_point, get_troop_by_period, Troop};
+
+/// 防衛軍のローテーション。同じ兵団が続く時間はひとつの区間にまとめる
+pub struct DefenseForce;
+
+impl Rotation for DefenseForce {
+    type Item = Troop;
+
+    fn current_at(&self, dt: DateTime<Utc>) -> Option<Occurrence<Troop>> {
+        let period = calc_period(&dt).ok()?;
+        let troop = get_troop_by_period(period).ok()?;
+
+        let mut first = period;
+        while first > 0 && get_troop_by_period(first - 1).ok()? == troop {
+            first -= 1;
+        }
+        let mut last = period;
+        while get_troop_by_period(last + 1).ok()? == troop {
+            last += 1;
+        }
+
+        let base_point = Utc.from_utc_datetime(&get_base_point().ok()?);
+        Some(Occurrence {
+            value: troop,
+            start: base_point.checked_add_signed(Duration::try_hours(first as i64)?)?,
+            end: base_point.checked_add_signed(Duration::try_hours(last as i64 + 1)?)?,
+        })
+    }
+}
@@ -1,63 +1,151 @@
 use super::troop::*;
-use chrono::{DateTime, Datelike, Duration, Local, TimeZone, Timelike, Utc};
+use super::DefenseForce;
+use crate::rotation::{Occurrence, Rotation};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Serialize, Serializer};
+use crate::clock::Clock;
+use crate::error::{self, Error};
 
-pub struct Event {
-    pub started_at: DateTime<Local>,
+#[derive(Serialize)]
+#[serde(bound(serialize = ""))]
+pub struct Event<Tz: TimeZone> {
+    pub started_at: DateTime<Tz>,
     pub troop: Troop
 }
 
-pub fn get_current_schedule() -> Option<Vec<Event>> {
-    get_schedule_in(Utc::now(), 24)
+/// ある兵団が続けて出現する期間
+#[derive(Serialize)]
+#[serde(bound(serialize = ""))]
+pub struct Window<Tz: TimeZone> {
+    pub troop: Troop,
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+    /// JSONでは分単位
+    #[serde(serialize_with = "serialize_minutes")]
+    pub duration: Duration,
+    /// 指定日時から期間の終わりまで。まだ始まっていなければdurationと同じ。JSONでは分単位
+    /// State::next_inと同じく、秒以下を切り捨てた時刻から数える
+    #[serde(serialize_with = "serialize_minutes")]
+    pub remaining: Duration,
 }
 
-pub fn get_current_schedule_in(count: usize) -> Option<Vec<Event>> {
-    get_schedule_in(Utc::now(), count)
+impl<Tz: TimeZone> Window<Tz> {
+    fn from_occurrence(o: Occurrence<Troop>, dt: DateTime<Utc>, tz: &Tz) -> Window<Tz> {
+        let truncated = super::state::truncate_to_minute(dt);
+        Window {
+            troop: o.value,
+            start: o.start.with_timezone(tz),
+            end: o.end.with_timezone(tz),
+            duration: o.end - o.start,
+            remaining: o.end - truncated.max(o.start),
+        }
+    }
+
+    /// 指定日時に出現中か
+    pub fn is_current_at(&self, dt: DateTime<Utc>) -> bool {
+        self.start.to_utc() <= dt && dt < self.end.to_utc()
+    }
+}
+
+fn serialize_minutes<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(duration.num_minutes())
+}
+
+/// 指定された日時以降に兵団が出現する期間をcount件取得する。指定日時に出現中の期間も含む
+pub fn find_windows<Tz: TimeZone>(troop: Troop, dt: DateTime<Utc>, count: usize, tz: Tz) -> Vec<Window<Tz>> {
+    DefenseForce.find(&troop, dt, count).into_iter()
+        .map(|o| Window::from_occurrence(o, dt, &tz))
+        .collect()
+}
+
+pub fn get_current_window<Tz: TimeZone>(clock: &impl Clock, tz: Tz) -> error::Result<Window<Tz>> {
+    get_window(clock.now(), tz)
+}
+
+/// 指定日時に出現中の兵団の期間。remainingは残り時間
+pub fn get_window<Tz: TimeZone>(dt: DateTime<Utc>, tz: Tz) -> error::Result<Window<Tz>> {
+    calc_period(&dt)?;
+    let occurrence = DefenseForce.current_at(dt).ok_or(Error::TimestampOutOfRange(dt.timestamp()))?;
+    Ok(Window::from_occurrence(occurrence, dt, &tz))
+}
+
+/// get_schedule_inと同じcount時間分の兵団の期間。最初の期間は指定日時に出現中のもので、指定日時より前に始まることがある
+pub fn get_windows_in<Tz: TimeZone>(dt: DateTime<Utc>, count: usize, tz: Tz) -> error::Result<Vec<Window<Tz>>> {
+    let period = calc_period(&dt)?;
+    let limit = end_of_periods(dt, period, count)?;
+    Ok(DefenseForce.upcoming(dt)
+        .take_while(|o| o.start < limit)
+        .map(|o| Window::from_occurrence(o, dt, &tz))
+        .collect())
 }
 
-pub fn get_schedule(dt: DateTime<Utc>) -> Option<Vec<Event>> {
-    get_schedule_in(dt, 24)
+/// periodからcount時間後の時刻。表せなければエラー
+fn end_of_periods(dt: DateTime<Utc>, period: usize, count: usize) -> error::Result<DateTime<Utc>> {
+    let base_point = get_base_point()?;
+    period.checked_add(count)
+        .and_then(|end| i64::try_from(end).ok())
+        .and_then(Duration::try_hours)
+        .and_then(|elapsed| base_point.checked_add_signed(elapsed))
+        .map(|end| Utc.from_utc_datetime(&end))
+        .ok_or(Error::TimestampOutOfRange(dt.timestamp()))
+}
+
+pub fn get_current_schedule<Tz: TimeZone>(clock: &impl Clock, tz: Tz) -> error::Result<Vec<Event<Tz>>> {
+    get_schedule_in(clock.now(), 24, tz)
+}
+
+pub fn get_current_schedule_in<Tz: TimeZone>(clock: &impl Clock, count: usize, tz: Tz) -> error::Result<Vec<Event<Tz>>> {
+    get_schedule_in(clock.now(), count, tz)
+}
+
+pub fn get_schedule<Tz: TimeZone>(dt: DateTime<Utc>, tz: Tz) -> error::Result<Vec<Event<Tz>>> {
+    get_schedule_in(dt, 24, tz)
 }
 
 /// 指定された日時を起点として、防衛軍イベントを24時間分取得する
 /// epoch_millis: i64 - Unix epochからの経過時間(ミリ秒)
-pub fn get_schedule_from_epoch_millis(epoch_millis:i64) -> Option<Vec<Event>> {
+pub fn get_schedule_from_epoch_millis<Tz: TimeZone>(epoch_millis:i64, tz: Tz) -> error::Result<Vec<Event<Tz>>> {
     let result = Utc.timestamp_millis_opt(epoch_millis);
-    let dt = result.single()?;
-    get_schedule(dt)
+    // TimestampOutOfRangeは秒で表すので、ミリ秒を切り捨てて渡す
+    let dt = result.single().ok_or(Error::TimestampOutOfRange(epoch_millis.div_euclid(1000)))?;
+    get_schedule(dt, tz)
 }
 
-pub fn get_schedule_in(dt: DateTime<Utc>, count: usize) -> Option<Vec<Event>> {
-    calc_period(&dt).ok().and_then( |period| {
-        Utc.with_ymd_and_hms(dt.year(), dt.month(), dt.day(), dt.hour(), 0, 0).single().map( |started_at| {
-            let mut vec: Vec<Event> = Vec::with_capacity(24);
-
-            let troop = get_troop_by_period(period);
-
-            vec.push(Event{
-                started_at: started_at.with_timezone(&Local),
-                troop
-            });
-
-            for i in 1..count {
-                let troop = get_troop_by_period(period + i);
-                let prev_troop = get_troop_by_period(period + i - 1);
-
-                if troop == prev_troop {
-                    continue;
-                }
-
-                match Duration::try_hours(i as i64) {
-                    Some(duration) => {
-                        vec.push(Event{
-                            started_at: (started_at + duration).with_timezone(&Local),
-                            troop
-                        })
-                    },
-                    None => break
-                }
+pub fn get_schedule_in<Tz: TimeZone>(dt: DateTime<Utc>, count: usize, tz: Tz) -> error::Result<Vec<Event<Tz>>> {
+    calc_period(&dt).and_then( |period| {
+        end_of_periods(dt, period, count)?;
+        let out_of_range = || Error::TimestampOutOfRange(dt.timestamp());
+        let elapsed = Duration::try_hours(period as i64).ok_or_else(out_of_range)?;
+        let started_at = Utc.from_utc_datetime(&get_base_point()?.checked_add_signed(elapsed).ok_or_else(out_of_range)?);
+        let mut vec: Vec<Event<Tz>> = Vec::with_capacity(24);
+
+        let mut prev_troop = get_troop_by_period(period)?;
+
+        vec.push(Event{
+            started_at: started_at.with_timezone(&tz),
+            troop: prev_troop
+        });
+
+        for i in 1..count {
+            let troop = get_troop_by_period(period.checked_add(i).ok_or_else(out_of_range)?)?;
+
+            if troop == prev_troop {
+                continue;
+            }
+            prev_troop = troop;
+
+            let started = Duration::try_hours(i as i64).and_then(|duration| started_at.checked_add_signed(duration));
+            match started {
+                Some(started) => {
+                    vec.push(Event{
+                        started_at: started.with_timezone(&tz),
+                        troop
+                    })
+                },
+                None => break
             }
-            vec
-        })
+        }
+        Ok(vec)
     })
 }
 
@@ -66,15 +154,15 @@ mod tests {
     use chrono::prelude::*;
     use chrono_tz;
     #[test]
-    fn test_get_schedule_none_before_basepoint() {
+    fn test_get_schedule_error_before_basepoint() {
         let dt = chrono::Local.with_ymd_and_hms(2018, 9, 22, 23, 45, 10).single().unwrap();
-        let schedule = super::get_schedule(dt.to_utc());
-        assert!(schedule.is_none());
+        let schedule = super::get_schedule(dt.to_utc(), chrono::Local);
+        assert!(matches!(schedule, Err(crate::error::Error::BeforeBasePoint(_))));
     }
 
     /// 周期の一番最初のスケジュールを確認するテスト
     fn assert_first_time(dt: chrono::DateTime<Utc>) {
-        let schedule = super::get_schedule(dt).unwrap();
+        let schedule = super::get_schedule(dt, chrono_tz::Asia::Tokyo).unwrap();
         assert_eq!(schedule.len(), 24);
         assert_eq!(schedule[0].troop.name(), "金神の遺宝兵団");
         assert_eq!(schedule[1].troop.name(), "紅爆の暴賊兵団");
@@ -107,12 +195,100 @@ mod tests {
         assert_first_time(dt.to_utc());
     }
     #[test]
+    fn test_get_schedule_in_time_zone() {
+        // 同じ時刻でも、指定したタイムゾーンの時差で表す
+        let dt = chrono::Utc.with_ymd_and_hms(2025, 12, 9, 21, 10, 34).single().unwrap();
+        let jst = super::get_schedule(dt, chrono_tz::Asia::Tokyo).unwrap();
+        let est = super::get_schedule(dt, chrono_tz::America::New_York).unwrap();
+        assert_eq!(jst[0].started_at, est[0].started_at);
+        assert_eq!(jst[0].started_at.to_rfc3339(), "2025-12-10T06:00:00+09:00");
+        assert_eq!(est[0].started_at.to_rfc3339(), "2025-12-09T16:00:00-05:00");
+    }
+    #[test]
     fn text_get_schedule_from_epoch_millis() {
         // 2025-12-15T12:00:00+09:00  // bird -> steel
         let epoch = 1765767600000;
-        let schedule = super::get_schedule_from_epoch_millis(epoch).unwrap();
+        let schedule = super::get_schedule_from_epoch_millis(epoch, chrono_tz::Asia::Tokyo).unwrap();
         assert_eq!(schedule[0].troop.name(), "白雲の冥翼兵団");
         assert_eq!(schedule[1].troop.name(), "鋼塊の重滅兵団");
+
+        let result = super::get_schedule_from_epoch_millis(i64::MAX, chrono_tz::Asia::Tokyo);
+        assert_eq!(result.err(), Some(super::Error::TimestampOutOfRange(i64::MAX / 1000)));
+        let result = super::get_schedule_from_epoch_millis(-9_000_000_000_000_001, chrono_tz::Asia::Tokyo);
+        assert_eq!(result.err(), Some(super::Error::TimestampOutOfRange(-9_000_000_000_001)));
+    }
+
+    #[test]
+    fn test_find_windows() {
+        // 彩虹の粘塊兵団は起点から15時間後に出現し、以降30時間ごとに出現する
+        let slime = super::find_troop("スライム").unwrap();
+        let dt = chrono_tz::Asia::Tokyo.with_ymd_and_hms(2025, 12, 10, 6, 0, 0).single().unwrap();
+        let windows = super::find_windows(slime, dt.to_utc(), 3, chrono_tz::Asia::Tokyo);
+        assert_eq!(windows.len(), 3);
+        let start = windows[0].start.with_timezone(&chrono_tz::Asia::Tokyo);
+        assert_eq!((start.day(), start.hour()), (10, 21));
+        assert_eq!(windows[0].duration, chrono::Duration::hours(1));
+        assert_eq!(windows[1].start - windows[0].start, chrono::Duration::hours(30));
+        assert_eq!(windows[2].start - windows[1].start, chrono::Duration::hours(30));
+    }
+
+    #[test]
+    fn test_find_windows_includes_current() {
+        let gold = super::find_troop("金神の遺宝兵団").unwrap();
+        let dt = chrono_tz::Asia::Tokyo.with_ymd_and_hms(2025, 12, 10, 6, 30, 0).single().unwrap();
+        let windows = super::find_windows(gold, dt.to_utc(), 2, chrono::Utc);
+        assert_eq!(windows[0].start.to_utc(), chrono::Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap());
+        assert_eq!(windows[0].end.to_utc(), chrono::Utc.with_ymd_and_hms(2025, 12, 9, 22, 0, 0).unwrap());
+        // 次は10時間後
+        assert_eq!(windows[1].start.to_utc(), chrono::Utc.with_ymd_and_hms(2025, 12, 10, 7, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_get_window_remaining() {
+        // 起点から36時間後。白雲の冥翼兵団は19:00まで
+        let dt = chrono_tz::Asia::Tokyo.with_ymd_and_hms(2025, 12, 11, 18, 4, 30).single().unwrap();
+        let window = super::get_window(dt.to_utc(), chrono_tz::Asia::Tokyo).unwrap();
+        assert_eq!(window.troop.name(), "白雲の冥翼兵団");
+        assert_eq!(window.end.to_rfc3339(), "2025-12-11T19:00:00+09:00");
+        assert!(window.is_current_at(dt.to_utc()));
+        // 残り時間はnext_inと同じ
+        let state = crate::defense_force::state::get_state(dt.to_utc(), chrono_tz::Asia::Tokyo).unwrap();
+        assert_eq!(window.remaining.num_minutes(), state.next_in as i64);
+        assert_eq!(window.end, state.changed_at);
+
+        let clock = crate::clock::FixedClock(dt.to_utc());
+        assert_eq!(super::get_current_window(&clock, chrono::Utc).unwrap().remaining, window.remaining);
+    }
+
+    #[test]
+    fn test_get_windows_in() {
+        let dt = chrono_tz::Asia::Tokyo.with_ymd_and_hms(2025, 12, 10, 6, 30, 0).single().unwrap();
+        let windows = super::get_windows_in(dt.to_utc(), 3, chrono_tz::Asia::Tokyo).unwrap();
+        let names: Vec<&str> = windows.iter().map(|w| w.troop.name()).collect();
+        assert_eq!(names, ["金神の遺宝兵団", "紅爆の暴賊兵団", "全兵団"]);
+        // 出現中の期間は途中から
+        assert_eq!(windows[0].start.to_rfc3339(), "2025-12-10T06:00:00+09:00");
+        assert_eq!(windows[0].duration, chrono::Duration::hours(1));
+        assert_eq!(windows[0].remaining, chrono::Duration::minutes(30));
+        // 先の期間は全体が残っている
+        assert_eq!(windows[1].remaining, windows[1].duration);
+        assert!(!windows[1].is_current_at(dt.to_utc()));
+        for pair in windows.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
     }
-}
 
+    #[test]
+    fn test_get_windows_in_before_basepoint_is_error() {
+        let dt = chrono::Utc.with_ymd_and_hms(2018, 9, 22, 0, 0, 0).single().unwrap();
+        assert!(matches!(super::get_windows_in(dt, 3, chrono::Utc), Err(crate::error::Error::BeforeBasePoint(_))));
+        assert!(super::get_window(dt, chrono::Utc).is_err());
+    }
+
+    #[test]
+    fn test_get_windows_in_too_many_is_error() {
+        let dt = chrono::Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).single().unwrap();
+        assert!(matches!(super::get_windows_in(dt, usize::MAX, chrono::Utc), Err(crate::error::Error::TimestampOutOfRange(_))));
+        assert!(matches!(super::get_schedule_in(dt, usize::MAX, chrono::Utc), Err(crate::error::Error::TimestampOutOfRange(_))));
+    }
+}
@@ -1,43 +1,54 @@
 use crate::defense_force::troop::*;
-use chrono::{DateTime, Local, Duration, Timelike, Utc};
+use chrono::{DateTime, Duration, TimeZone, Timelike, Utc};
+use serde::Serialize;
+use crate::clock::Clock;
+use crate::error::{Error, Result};
 
-pub struct State {
+#[derive(Serialize)]
+#[serde(bound(serialize = ""))]
+pub struct State<Tz: TimeZone> {
     pub troop: Troop,
     pub next_troop: Troop,
     pub next_in: u32,
-    pub changed_at: chrono::DateTime<Local>
+    pub changed_at: chrono::DateTime<Tz>
 }
 
-pub fn get_current_state() -> Option<State> {
-    get_state(Utc::now())
+pub fn get_current_state<Tz: TimeZone>(clock: &impl Clock, tz: Tz) -> Result<State<Tz>> {
+    get_state(clock.now(), tz)
 }
 
-pub fn get_state(dt: DateTime<Utc>) -> Option<State> {
-    calc_period(&dt).ok().and_then( |period| {
-        let mut next_in = 60 - dt.minute();
+pub fn get_state<Tz: TimeZone>(dt: DateTime<Utc>, tz: Tz) -> Result<State<Tz>> {
+    calc_period(&dt).and_then( |period| {
+        let elapsed = (dt.naive_utc() - get_base_point()?).num_minutes() % 60;
+        let mut next_in = 60 - elapsed as u32;
 
+        let troop = get_troop_by_period(period)?;
         let mut next_period = period + 1;
+        let mut next_troop = get_troop_by_period(next_period)?;
 
-        while is_same_troop(period, next_period) {
+        while next_troop == troop {
             next_period += 1;
+            next_troop = get_troop_by_period(next_period)?;
             next_in += 60;
         }
 
-        let duration = Duration::try_minutes(next_in as i64)?;
+        let duration = Duration::minutes(next_in as i64);
 
-        let changed_at = (dt + duration).with_timezone(&Local);
+        // next_inは分単位なので、秒以下を切り捨てた時刻から数える
+        let changed_at = truncate_to_minute(dt).checked_add_signed(duration).ok_or(Error::TimestampOutOfRange(dt.timestamp()))?.with_timezone(&tz);
 
-        Some(State {
-            troop: get_troop_by_period(period),
-            next_troop: get_troop_by_period(next_period),
+        Ok(State {
+            troop,
+            next_troop,
             next_in,
             changed_at,
         })
     })
 }
 
-fn is_same_troop(p1: usize, p2: usize) -> bool {
-    get_troop_by_period(p1) == get_troop_by_period(p2)
+/// 秒以下を切り捨てる。duration_truncはナノ秒で表せない2262年以降で失敗するので使わない
+pub(crate) fn truncate_to_minute(dt: DateTime<Utc>) -> DateTime<Utc> {
+    dt.with_second(0).and_then(|dt| dt.with_nanosecond(0)).unwrap_or(dt)
 }
 
 #[cfg(test)]
@@ -46,17 +57,17 @@ mod tests {
     use chrono_tz;
 
     #[test]
-    fn test_get_state_is_none_before_basepoint() {
+    fn test_get_state_is_error_before_basepoint() {
         let dt = chrono::Local.with_ymd_and_hms(2018, 9, 20, 15, 3, 15).single().unwrap();
-        let state = super::get_state(dt.to_utc());
-        assert!(state.is_none());
+        let state = super::get_state(dt.to_utc(), chrono::Local);
+        assert!(matches!(state, Err(crate::error::Error::BeforeBasePoint(_))));
     }
 
     #[test]
     fn test_get_state_jst() {
         // 起点から36時間後
         let dt = chrono_tz::Asia::Tokyo.with_ymd_and_hms(2025, 12, 11, 18, 4, 0).single().unwrap();
-        let state = super::get_state(dt.to_utc());
+        let state = super::get_state(dt.to_utc(), chrono_tz::Asia::Tokyo);
         let state = state.unwrap();
         assert!(state.troop.name().contains("冥翼"));
         assert!(state.next_troop.name().contains("重滅"));
@@ -66,10 +77,34 @@ mod tests {
     fn test_get_state_east0900() {
         // 起点から36時間後
         let dt = chrono::FixedOffset::east_opt(9 * 3600).unwrap().with_ymd_and_hms(2025, 12, 11, 18, 4, 0).single().unwrap();
-        let state = super::get_state(dt.to_utc());
+        let state = super::get_state(dt.to_utc(), chrono_tz::Asia::Tokyo);
         let state = state.unwrap();
         assert!(state.troop.name().contains("冥翼"));
         assert!(state.next_troop.name().contains("重滅"));
         assert_eq!(state.next_in, 56);
     }
+    #[test]
+    fn test_get_state_changed_at_is_on_the_hour() {
+        // 秒を切り捨てずに数えると19:00:30になっていた
+        let tz = chrono_tz::Asia::Tokyo;
+        let dt = tz.with_ymd_and_hms(2025, 12, 11, 18, 4, 30).single().unwrap();
+        let state = super::get_state(dt.to_utc(), tz).unwrap();
+        assert_eq!(state.changed_at, tz.with_ymd_and_hms(2025, 12, 11, 19, 0, 0).single().unwrap());
+        assert_ne!(state.changed_at, dt + chrono::Duration::minutes(state.next_in as i64));
+    }
+    #[test]
+    fn test_get_state_after_2262() {
+        // ナノ秒で表せない時刻でも分単位で数える
+        let dt = chrono::Utc.with_ymd_and_hms(2300, 1, 1, 0, 4, 30).single().unwrap();
+        let state = super::get_state(dt, chrono::Utc).unwrap();
+        assert_eq!(state.changed_at.second(), 0);
+        assert_eq!((state.changed_at.to_utc() - dt).num_seconds() + 30, state.next_in as i64 * 60);
+    }
+    #[test]
+    fn test_get_current_state_with_fixed_clock() {
+        let dt = chrono_tz::Asia::Tokyo.with_ymd_and_hms(2025, 12, 11, 18, 4, 0).single().unwrap();
+        let state = super::get_current_state(&crate::clock::FixedClock(dt.to_utc()), chrono_tz::Asia::Tokyo).unwrap();
+        assert!(state.troop.name().contains("冥翼"));
+        assert_eq!(state.next_in, 56);
+    }
 }
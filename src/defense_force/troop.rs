@@ -1,35 +1,41 @@
-use chrono::{DateTime, TimeZone, Utc, NaiveDateTime};
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum ColorStyle {
+use chrono::{DateTime, Duration, Utc, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use crate::data;
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorStyle {
     Standard(u32),
     Extended(u32),
     Rainbow,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub struct Troop {
+    id: &'static str,
     name: &'static str,
+    #[serde(skip)]
     color: ColorStyle,
 }
 
 impl Troop {
-    const fn standard(name: &'static str, code: u32) -> Self {
-        Self { name, color: ColorStyle::Standard(code) }
-    }
-
-    const fn extended(name: &'static str, code: u32) -> Self {
-        Self { name, color: ColorStyle::Extended(code) }
+    fn from_data(troop: &'static data::TroopData) -> Self {
+        Self { id: &troop.id, name: &troop.name, color: troop.color }
     }
 
-    const fn rainbow(name: &'static str) -> Self {
-        Self { name, color: ColorStyle::Rainbow }
+    pub fn id(&self) -> &'static str {
+        self.id
     }
 
     pub fn name(&self) -> &'static str {
         self.name
     }
 
+    pub fn color(&self) -> ColorStyle {
+        self.color
+    }
+
     pub fn colorized_name(&self) -> String {
         match self.color {
             ColorStyle::Standard(code) => {
@@ -47,80 +53,45 @@ impl Troop {
     }
 }
 
-const BEAST:     Troop = Troop::standard("闇朱の獣牙兵団", 31);
-const MACHINE:   Troop = Troop::standard("紫炎の鉄機兵団", 35);
-const GOLEM:     Troop = Troop::standard("深碧の造魔兵団", 32);
-const ZOMBIE:    Troop = Troop::standard("蒼怨の屍獄兵団", 34);
-const INSECT:    Troop = Troop::standard("銀甲の凶蟲兵団", 33);
-const MARINE:    Troop = Troop::standard("翠煙の海妖兵団", 36);
-const DRAGON:    Troop = Troop::standard("灰塵の竜鱗兵団", 37);
-const SLIME:     Troop = Troop::rainbow("彩虹の粘塊兵団");
-const FLOWER:    Troop = Troop::extended("芳墨の華烈兵団", 88);
-const BIRD:      Troop = Troop::standard("白雲の冥翼兵団", 0);
-const WOOD:      Troop = Troop::extended("腐緑の樹葬兵団", 100);
-const ALL:       Troop = Troop::standard("全兵団", 1);
-const VEGETABLE: Troop = Troop::extended("青鮮の菜果兵団", 190);
-const STEEL:     Troop = Troop::extended("鋼塊の重滅兵団", 103);
-const GOLD:      Troop = Troop::extended("金神の遺宝兵団", 220);
-const GANG:      Troop = Troop::extended("紅爆の暴賊兵団", 124);
-
-// 2025-12-10 6:00を起点とするスケジュール
-const CYCLE: [Troop; 30] = [
-    GOLD,
-    GANG,
-    ALL,
-    BEAST,
-    ZOMBIE,
-    DRAGON,
-    BIRD,
-    STEEL,
-    GANG,
-    ALL,
-    GOLD,
-    GANG,
-    ALL,
-    MACHINE,
-    INSECT,
-    SLIME,
-    WOOD,
-    STEEL,
-    GANG,
-    ALL,
-    GOLD,
-    GANG,
-    ALL,
-    GOLEM,
-    MARINE,
-    FLOWER,
-    VEGETABLE,
-    STEEL,
-    GANG,
-    ALL,
-];
-
-pub fn get_base_point() -> NaiveDateTime {
-    // JSTの2025-12-10 6時 = UTCの2025-12-09 21時
-    Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap().naive_utc()
+/// 最初のEraの開始時刻。periodはここからの経過時間(時間単位)
+pub fn get_base_point() -> Result<NaiveDateTime> {
+    Ok(data::try_get()?.defense_force.eras.first_start())
 }
 
-pub fn calc_period(dt: &DateTime<Utc>) -> Result<usize, String> {
-    let base_point = get_base_point();
+pub fn calc_period(dt: &DateTime<Utc>) -> Result<usize> {
+    let base_point = get_base_point()?;
     let start_time = dt.naive_utc();
     if start_time < base_point {
-        return Err(format!("no data before {}", base_point.format("%F %T")));
+        return Err(Error::BeforeBasePoint(base_point.and_utc()));
     }
     let idx = (start_time - base_point).num_hours() as usize;
     Ok(idx)
 }
 
-pub fn get_troop_by_period(p: usize) -> Troop {
-    let index = p % CYCLE.len();
-    CYCLE[index]
+/// periodの時刻に有効なEraのローテーションから兵団を決める。日時として表せないperiodはTimestampOutOfRange
+pub fn get_troop_by_period(p: usize) -> Result<Troop> {
+    let df = &data::try_get()?.defense_force;
+    let base_point = df.eras.first_start();
+    let out_of_range = || Error::TimestampOutOfRange(base_point.and_utc().timestamp().saturating_add((p as i64).saturating_mul(3600)));
+    let dt = i64::try_from(p).ok()
+        .and_then(Duration::try_hours)
+        .and_then(|elapsed| base_point.checked_add_signed(elapsed))
+        .ok_or_else(out_of_range)?;
+    // 最初のEraより後の時刻なので、区間の終わりが表せる限り見つかる。周期に含まれる兵団はデータ読み込み時に検証済み
+    let slot = df.eras.slot_at(dt).ok_or_else(out_of_range)?;
+    df.troop(slot.entry()).map(Troop::from_data)
+        .ok_or_else(|| Error::InvalidData(format!("defense_force: unknown troop {}", slot.entry())))
+}
+
+/// 兵団を名前で探す。探し方はdata::find_by_nameと同じ
+pub fn find_troop(query: &str) -> Result<Troop> {
+    data::find_by_name(&data::try_get()?.defense_force.troops, "troop", query).map(Troop::from_data)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
 
     #[test]
     fn test_calc_period_at_base_point() {
@@ -143,17 +114,24 @@ mod tests {
     #[test]
     fn test_calc_period_before_base_point_is_error() {
         let dt = Utc.with_ymd_and_hms(2025, 12, 9, 20, 0, 0).unwrap();
-        assert!(calc_period(&dt).is_err());
+        let base_point = Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap();
+        assert_eq!(calc_period(&dt), Err(Error::BeforeBasePoint(base_point)));
     }
 
     #[test]
     fn test_get_troop_by_period_first() {
-        assert_eq!(get_troop_by_period(0), GOLD);
+        assert_eq!(get_troop_by_period(0).unwrap().name(), "金神の遺宝兵団");
     }
 
     #[test]
     fn test_get_troop_by_period_last() {
-        assert_eq!(get_troop_by_period(29), ALL);
+        assert_eq!(get_troop_by_period(29).unwrap().name(), "全兵団");
+    }
+
+    #[test]
+    fn test_get_troop_by_period_out_of_range_is_error() {
+        assert!(matches!(get_troop_by_period(usize::MAX), Err(Error::TimestampOutOfRange(_))));
+        assert!(matches!(get_troop_by_period(i64::MAX as usize / 3600), Err(Error::TimestampOutOfRange(_))));
     }
 
     #[test]
@@ -162,11 +140,38 @@ mod tests {
         assert_eq!(get_troop_by_period(30), get_troop_by_period(0));
         assert_eq!(get_troop_by_period(60), get_troop_by_period(0));
         assert_eq!(get_troop_by_period(31), get_troop_by_period(1));
+        assert!(get_troop_by_period(30).is_ok());
     }
 
     #[test]
     fn test_get_troop_by_period_mid_cycle() {
-        assert_eq!(get_troop_by_period(3), BEAST);
-        assert_eq!(get_troop_by_period(15), SLIME);
+        assert_eq!(get_troop_by_period(3).unwrap().name(), "闇朱の獣牙兵団");
+        assert_eq!(get_troop_by_period(15).unwrap().name(), "彩虹の粘塊兵団");
+    }
+
+    #[test]
+    fn test_find_troop_by_name() {
+        assert_eq!(find_troop("彩虹の粘塊兵団").unwrap().name(), "彩虹の粘塊兵団");
+    }
+
+    #[test]
+    fn test_find_troop_by_alias() {
+        assert_eq!(find_troop("スライム").unwrap().name(), "彩虹の粘塊兵団");
+        assert_eq!(find_troop("slime").unwrap().name(), "彩虹の粘塊兵団");
+        assert_eq!(find_troop("GOLD").unwrap().name(), "金神の遺宝兵団");
+    }
+
+    #[test]
+    fn test_find_troop_by_substring() {
+        assert_eq!(find_troop("遺宝").unwrap().name(), "金神の遺宝兵団");
+        assert_eq!(find_troop("全").unwrap().name(), "全兵団");
+    }
+
+    #[test]
+    fn test_find_troop_errors() {
+        assert!(matches!(find_troop("存在しない"), Err(Error::UnknownName { kind: "troop", .. })));
+        assert!(find_troop("存在しない").unwrap_err().to_string().contains("金神の遺宝兵団"));
+        assert!(matches!(find_troop("兵団"), Err(Error::AmbiguousName { kind: "troop", .. })));
+        assert!(find_troop("").is_err());
     }
 }
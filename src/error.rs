@@ -0,0 +1,97 @@
+use std::fmt;
+use chrono::{DateTime, Utc};
+use chrono_tz::Asia::Tokyo;
+
+/// ライブラリの関数が返すエラー
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// ローテーションの起点より前の時刻が指定された
+    BeforeBasePoint(DateTime<Utc>),
+    /// 日時として表せないUnix時刻
+    TimestampOutOfRange(i64),
+    /// 夏時間の切り替わりなどで、ローカル時刻が一つに決まらないか存在しない
+    AmbiguousLocalTime(String),
+    /// 日時の書式が正しくない
+    InvalidDateTime { input: String, reason: String },
+    /// 該当する兵団やボスがない。kindは"troop"や"boss"
+    UnknownName { kind: &'static str, query: String, candidates: Vec<String> },
+    /// 兵団やボスの名前が複数に当てはまる
+    AmbiguousName { kind: &'static str, query: String, candidates: Vec<String> },
+    /// ローテーションデータが読めないか正しくない
+    InvalidData(String),
+    /// 入出力やネットワークのエラー
+    Io(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// コマンドの終了コード。2はclapが引数の誤りに使うので避ける
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io(_) => 1,
+            Error::InvalidDateTime { .. } => 3,
+            Error::TimestampOutOfRange(_) => 4,
+            Error::AmbiguousLocalTime(_) => 5,
+            Error::BeforeBasePoint(_) => 6,
+            Error::UnknownName { .. } => 7,
+            Error::AmbiguousName { .. } => 8,
+            Error::InvalidData(_) => 9,
+        }
+    }
+
+    pub(crate) fn invalid_date_time(input: &str, reason: impl fmt::Display) -> Error {
+        Error::InvalidDateTime { input: input.to_string(), reason: reason.to_string() }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::BeforeBasePoint(base_point) => write!(f, "no data before {}. specify a date time after it",
+                base_point.with_timezone(&Tokyo).format("%Y-%m-%d %H:%M JST")),
+            Error::TimestampOutOfRange(timestamp) => write!(f, "timestamp {} is out of range", timestamp),
+            Error::AmbiguousLocalTime(dt) => write!(f, "local time {} is ambiguous or does not exist. specify another time", dt),
+            Error::InvalidDateTime { input, reason } => write!(f, "invalid date time {}. {}", input, reason),
+            Error::UnknownName { kind, query, candidates } => write!(f, "unknown {} {}. choose from: {}", kind, query, candidates.join(", ")),
+            Error::AmbiguousName { kind, query, candidates } => write!(f, "ambiguous {} {}. did you mean: {}", kind, query, candidates.join(", ")),
+            Error::InvalidData(message) => write!(f, "invalid rotation data. {}", message),
+            Error::Io(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use super::Error;
+
+    #[test]
+    fn test_display() {
+        let base_point = chrono::Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap();
+        assert_eq!(Error::BeforeBasePoint(base_point).to_string(), "no data before 2025-12-10 06:00 JST. specify a date time after it");
+        let e = Error::AmbiguousName { kind: "troop", query: "兵団".to_string(), candidates: vec!["全兵団".to_string(), "金神の遺宝兵団".to_string()] };
+        assert_eq!(e.to_string(), "ambiguous troop 兵団. did you mean: 全兵団, 金神の遺宝兵団");
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let errors = [
+            Error::Io(String::new()),
+            Error::invalid_date_time("", ""),
+            Error::TimestampOutOfRange(0),
+            Error::AmbiguousLocalTime(String::new()),
+            Error::BeforeBasePoint(chrono::Utc::now()),
+            Error::UnknownName { kind: "troop", query: String::new(), candidates: vec![] },
+            Error::AmbiguousName { kind: "troop", query: String::new(), candidates: vec![] },
+            Error::InvalidData(String::new()),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(Error::exit_code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+        assert!(!codes.contains(&0) && !codes.contains(&2));
+    }
+}
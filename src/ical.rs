@@ -0,0 +1,239 @@
+use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::Asia::Tokyo;
+use crate::defense_force::{schedule, DefenseForce};
+use crate::levels::{self, LevelsContent};
+use crate::panigarm::{self, Panigarm};
+use crate::rotation::Rotation;
+
+const PRODID: &str = "-//paulownia//dq10tools//JA";
+const TZID: &str = "Asia/Tokyo";
+
+/// カレンダーのイベント
+#[derive(Debug, Clone, PartialEq)]
+pub struct VEvent {
+    pub uid: String,
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl VEvent {
+    /// UIDは種類と開始時刻から決まるので、同じイベントは何度生成しても同じUIDになる
+    pub fn new(kind: &str, summary: String, start: DateTime<Utc>, end: DateTime<Utc>) -> VEvent {
+        let uid = format!("{}-{}@dq10tools", kind, start.format("%Y%m%dT%H%M%SZ"));
+        VEvent { uid, summary, start, end }
+    }
+}
+
+/// 防衛軍のスケジュールをイベントにする。終了時刻は次の兵団の開始時刻
+pub fn from_schedule<Tz: TimeZone>(schedule: &[schedule::Event<Tz>]) -> Vec<VEvent> {
+    let starts: Vec<DateTime<Utc>> = schedule.iter().map(|e| e.started_at.to_utc()).collect();
+    schedule.iter().enumerate().filter_map(|(i, event)| {
+        let end = match starts.get(i + 1) {
+            Some(end) => *end,
+            None => DefenseForce.next_change_after(starts[i])?,
+        };
+        Some(VEvent::new("defense-force", event.troop.name().to_string(), starts[i], end))
+    }).collect()
+}
+
+/// 防衛軍の兵団の期間をイベントにする
+pub fn from_windows<Tz: TimeZone>(windows: &[schedule::Window<Tz>]) -> Vec<VEvent> {
+    windows.iter()
+        .map(|w| VEvent::new("defense-force", w.troop.name().to_string(), w.start.to_utc(), w.end.to_utc()))
+        .collect()
+}
+
+/// パニガルムのボスの周期をイベントにする。終了時刻は次のボスの開始時刻
+pub fn from_cycle<Tz: TimeZone>(cycle: &[panigarm::Event<Tz>]) -> Vec<VEvent> {
+    let starts: Vec<DateTime<Utc>> = cycle.iter().map(|e| e.started_at.to_utc()).collect();
+    cycle.iter().enumerate().filter_map(|(i, event)| {
+        let end = match starts.get(i + 1) {
+            Some(end) => *end,
+            None => Panigarm.next_change_after(starts[i])?,
+        };
+        Some(VEvent::new("panigarm", event.boss.name.to_string(), starts[i], end))
+    }).collect()
+}
+
+/// 日替わりのレベルを指定日数分イベントにする
+pub fn from_levels<C: LevelsContent>(content: &C, dt: DateTime<Utc>, days: usize) -> Vec<VEvent> {
+    content.upcoming(dt).take(days).map(|occurrence| {
+        let summary = format!("{} {}", C::TITLE, levels::format_levels(&occurrence.value));
+        VEvent::new(C::KIND, summary, occurrence.start, occurrence.end)
+    }).collect()
+}
+
+/// fromからtoの間に始まる区間をイベントにする。summaryがNoneを返す区間は含めない
+pub fn from_rotation<R: Rotation>(rotation: &R, kind: &str, from: DateTime<Utc>, to: DateTime<Utc>, summary: impl Fn(&R::Item) -> Option<String>) -> Vec<VEvent> {
+    rotation.upcoming(from)
+        .take_while(|occurrence| occurrence.start < to)
+        .filter_map(|occurrence| {
+            summary(&occurrence.value).map(|summary| VEvent::new(kind, summary, occurrence.start, occurrence.end))
+        })
+        .collect()
+}
+
+/// VCALENDARとして出力する。日時はアストルティアの基準であるAsia/Tokyoで表す
+pub fn to_ics(name: &str, events: &[VEvent], dtstamp: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape(name)),
+        format!("X-WR-TIMEZONE:{}", TZID),
+        "BEGIN:VTIMEZONE".to_string(),
+        format!("TZID:{}", TZID),
+        "BEGIN:STANDARD".to_string(),
+        "DTSTART:19700101T000000".to_string(),
+        "TZOFFSETFROM:+0900".to_string(),
+        "TZOFFSETTO:+0900".to_string(),
+        "TZNAME:JST".to_string(),
+        "END:STANDARD".to_string(),
+        "END:VTIMEZONE".to_string(),
+    ];
+
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", event.uid));
+        lines.push(format!("DTSTAMP:{}", dtstamp.format("%Y%m%dT%H%M%SZ")));
+        lines.push(format!("DTSTART;TZID={}:{}", TZID, to_local(event.start)));
+        lines.push(format!("DTEND;TZID={}:{}", TZID, to_local(event.end)));
+        lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+        lines.push("TRANSP:TRANSPARENT".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+fn to_local(dt: DateTime<Utc>) -> String {
+    dt.with_timezone(&Tokyo).format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// TEXT型の値のエスケープ (RFC 5545 3.3.11)
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// 75オクテットを超える行を折り返す (RFC 5545 3.1)。マルチバイト文字の途中では折り返さない
+fn fold(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut width = 0;
+    for ch in line.chars() {
+        let len = ch.len_utf8();
+        if width + len > 75 {
+            result.push_str("\r\n ");
+            width = 1;
+        }
+        result.push(ch);
+        width += len;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::prelude::*;
+    use crate::defense_force::schedule;
+    use crate::panigarm;
+    use crate::sinners::Sinners;
+
+    fn jst(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        chrono_tz::Asia::Tokyo.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap().to_utc()
+    }
+
+    #[test]
+    fn test_from_schedule() {
+        let schedule = schedule::get_schedule_in(jst(2025, 12, 10, 6), 3, chrono_tz::Asia::Tokyo).unwrap();
+        let events = super::from_schedule(&schedule);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].summary, "金神の遺宝兵団");
+        assert_eq!(events[0].uid, "defense-force-20251209T210000Z@dq10tools");
+        assert_eq!(events[0].start, jst(2025, 12, 10, 6));
+        assert_eq!(events[0].end, jst(2025, 12, 10, 7));
+        // 最後のイベントの終了時刻は次の兵団への切り替わり
+        assert_eq!(events[2].summary, "全兵団");
+        assert_eq!(events[2].end, jst(2025, 12, 10, 9));
+    }
+
+    #[test]
+    fn test_from_windows() {
+        let windows = schedule::get_windows_in(jst(2025, 12, 10, 6), 3, chrono_tz::Asia::Tokyo).unwrap();
+        let events = super::from_windows(&windows);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].summary, "金神の遺宝兵団");
+        assert_eq!(events[0].uid, "defense-force-20251209T210000Z@dq10tools");
+        assert_eq!(events[0].end, jst(2025, 12, 10, 7));
+        assert_eq!(events[2].summary, "全兵団");
+        assert_eq!(events[2].end, jst(2025, 12, 10, 9));
+    }
+
+    #[test]
+    fn test_from_cycle() {
+        let cycle = panigarm::get_cycle(jst(2025, 1, 29, 6), chrono_tz::Asia::Tokyo).unwrap();
+        let events = super::from_cycle(&cycle);
+        assert_eq!(events.len(), 8);
+        assert_eq!(events[0].summary, "源世鳥アルマナ");
+        assert_eq!(events[0].end, jst(2025, 2, 1, 6));
+        assert_eq!(events[7].end, jst(2025, 2, 22, 6));
+    }
+
+    #[test]
+    fn test_from_levels() {
+        let events = super::from_levels(&Sinners, jst(2022, 5, 11, 12), 2);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary, "アビスの咎人 絶念のアウルモッド 2, 狂禍のフラウソン 1, 悲愴のウィリーデ 2, 燦滅のノクゼリア 1");
+        assert_eq!(events[0].start, jst(2022, 5, 11, 6));
+        assert_eq!(events[1].start, jst(2022, 5, 12, 6));
+    }
+
+    #[test]
+    fn test_from_rotation() {
+        use crate::defense_force::DefenseForce;
+        // 金神の遺宝兵団は10時間ごと
+        let from = jst(2025, 12, 10, 6);
+        let events = super::from_rotation(&DefenseForce, "defense-force", from, from + chrono::Duration::days(1), |troop| {
+            (troop.id() == "GOLD").then(|| troop.name().to_string())
+        });
+        let starts: Vec<_> = events.iter().map(|e| e.start).collect();
+        assert_eq!(starts, [jst(2025, 12, 10, 6), jst(2025, 12, 10, 16), jst(2025, 12, 11, 2)]);
+        assert!(events.iter().all(|e| e.end - e.start == chrono::Duration::hours(1)));
+    }
+
+    #[test]
+    fn test_to_ics() {
+        let event = super::VEvent::new("panigarm", "源世鳥アルマナ".to_string(), jst(2025, 1, 29, 6), jst(2025, 2, 1, 6));
+        let ics = super::to_ics("パニガルム", &[event], Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nUID:panigarm-20250128T210000Z@dq10tools\r\n"));
+        assert!(ics.contains("\r\nDTSTAMP:20250101T000000Z\r\n"));
+        assert!(ics.contains("\r\nDTSTART;TZID=Asia/Tokyo:20250129T060000\r\n"));
+        assert!(ics.contains("\r\nDTEND;TZID=Asia/Tokyo:20250201T060000\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:源世鳥アルマナ\r\n"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(super::escape("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+    }
+
+    #[test]
+    fn test_fold() {
+        let line = format!("SUMMARY:{}", "あ".repeat(30));
+        let folded = super::fold(&line);
+        let lines: Vec<&str> = folded.split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.len() <= 75));
+        assert_eq!(lines[0].len(), 8 + 22 * 3);
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Serialize, Serializer};
+use crate::data;
+use crate::error;
+use crate::rotation::{Occurrence, Rotation};
+
+/// 日替わりでボスのレベルが変わるコンテンツ。アビスの咎人と聖守護者は使うデータだけが異なる
+pub trait LevelsContent {
+    /// 表示名
+    const TITLE: &'static str;
+    /// iCalendarのUIDなどに使う識別子
+    const KIND: &'static str;
+
+    /// データファイルのうち、このコンテンツの定義
+    fn data(data: &data::Data) -> &data::LevelsData;
+}
+
+/// 日替わりでレベルが変わるボス。IDと名前はデータファイルから読む。JSONへの出力では名前で表す
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Boss {
+    id: &'static str,
+    name: &'static str,
+}
+
+impl Boss {
+    pub(crate) fn from_data(boss: &'static data::BossData) -> Self {
+        Boss { id: &boss.id, name: &boss.name }
+    }
+
+    pub fn id(&self) -> &'static str {
+        self.id
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl Serialize for Boss {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name)
+    }
+}
+
+/// 日替わりで変わるボスのレベル
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
+pub struct Level(pub u32);
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// レベルが有効な期間。toはその期間の最後の秒
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(bound(serialize = ""))]
+pub struct Term<Tz: TimeZone> {
+    pub from: DateTime<Tz>,
+    pub to: DateTime<Tz>,
+}
+
+/// ある期間のボスごとのレベル。JSONでは {"level": {ボス名: レベル}, "term": {"from", "to"}} になる
+#[derive(Debug, Clone, Serialize)]
+#[serde(bound(serialize = ""))]
+pub struct Levels<Tz: TimeZone> {
+    #[serde(rename = "level", serialize_with = "serialize_levels")]
+    pub levels: Vec<(Boss, Level)>,
+    pub term: Term<Tz>,
+}
+
+impl<Tz: TimeZone> Levels<Tz> {
+    /// IDで指定したボスのレベル
+    pub fn level(&self, id: &str) -> Option<Level> {
+        self.levels.iter().find(|(boss, _)| boss.id == id).map(|(_, level)| *level)
+    }
+}
+
+/// 指定時刻のレベルと、その期間をtzで表したもの。期間はEraの周期から決まる
+pub fn get_levels<C: LevelsContent, Tz: TimeZone>(now: DateTime<Utc>, tz: Tz) -> error::Result<Levels<Tz>> {
+    let content = C::data(data::try_get()?);
+    let slot = content.eras.try_slot_at(now.naive_utc())?;
+
+    let from = tz.from_utc_datetime(&slot.start);
+    let to = tz.from_utc_datetime(&slot.end) - Duration::seconds(1);
+
+    Ok(Levels {
+        levels: to_levels(&content.bosses, slot.entry()),
+        term: Term { from, to },
+    })
+}
+
+impl<C: LevelsContent> Rotation for C {
+    type Item = Vec<(Boss, Level)>;
+
+    fn current_at(&self, dt: DateTime<Utc>) -> Option<Occurrence<Self::Item>> {
+        let content = C::data(data::try_get().ok()?);
+        let slot = content.eras.slot_at(dt.naive_utc())?;
+        Some(Occurrence {
+            value: to_levels(&content.bosses, slot.entry()),
+            start: Utc.from_utc_datetime(&slot.start),
+            end: Utc.from_utc_datetime(&slot.end),
+        })
+    }
+}
+
+/// "ボス名 レベル"をカンマ区切りで並べる
+pub fn format_levels(levels: &[(Boss, Level)]) -> String {
+    levels.iter().map(|(boss, level)| format!("{} {}", boss.name, level)).collect::<Vec<_>>().join(", ")
+}
+
+/// データのボスの順に、周期の要素のレベルを対応させる
+fn to_levels(bosses: &'static [data::BossData], levels: &[u32]) -> Vec<(Boss, Level)> {
+    bosses.iter().map(Boss::from_data).zip(levels)
+        .map(|(boss, level)| (boss, Level(*level)))
+        .collect()
+}
+
+fn serialize_levels<S: Serializer>(levels: &[(Boss, Level)], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(levels.iter().map(|(boss, level)| (boss, level)))
+}
@@ -1,5 +1,20 @@
 pub mod saint_guardians;
+pub mod data;
 pub mod ast;
+pub mod clock;
 pub mod defense_force;
+pub mod error;
+pub mod ical;
+pub mod levels;
+pub mod metrics;
+pub mod notify;
 pub mod panigarm;
+pub mod rotation;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod sinners;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+pub mod zone;
+#[cfg(feature = "cli")]
+pub mod cli;
@@ -0,0 +1,117 @@
+use std::fmt::Write;
+use chrono::{DateTime, Timelike, Utc};
+use crate::ast;
+use crate::data;
+use crate::defense_force::DefenseForce;
+use crate::levels::{Boss, Level};
+use crate::panigarm::Panigarm;
+use crate::rotation::Rotation;
+use crate::saint_guardians::SaintGuardians;
+use crate::sinners::Sinners;
+
+/// Prometheusのテキスト形式でのContent-Type
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// 指定時刻の各コンテンツの状態をPrometheusのテキスト形式で出力する
+pub fn render(now: DateTime<Utc>) -> String {
+    let mut metrics = Metrics::default();
+
+    if let Ok(ast) = ast::from_datetime(now) {
+        metrics.gauge("dq10_ast_hour", "Astoltia time hour", &[], ast.time().hour() as f64);
+        metrics.gauge("dq10_ast_day", "1 while it is day in Astoltia, 0 at night", &[], (ast.state() == ast::State::Day) as u8 as f64);
+        metrics.gauge("dq10_ast_state_change_seconds", "Seconds until the next day/night change", &[], seconds(ast.until_state_change()));
+    }
+
+    if let (Some(current), Ok(data)) = (DefenseForce.current_at(now), data::try_get()) {
+        let troop = current.value;
+        let index = data.defense_force.troops.iter().position(|t| t.id == troop.id()).unwrap_or_default();
+        let labels = [("id", troop.id()), ("name", troop.name())];
+        metrics.gauge("dq10_defense_force_troop_index", "Index of the current defense force troop", &labels, index as f64);
+        metrics.gauge("dq10_defense_force_change_seconds", "Seconds until the next defense force troop", &[], seconds(current.end - now));
+    }
+
+    if let (Some(current), Ok(data)) = (Panigarm.current_at(now), data::try_get()) {
+        let boss = current.value;
+        let index = data.panigarm.bosses.iter().position(|b| b.id == boss.id).unwrap_or_default();
+        let labels = [("id", boss.id), ("name", boss.name)];
+        metrics.gauge("dq10_panigarm_boss_index", "Index of the current Panigarm boss", &labels, index as f64);
+        metrics.gauge("dq10_panigarm_change_seconds", "Seconds until the next Panigarm boss", &[], seconds(current.end - now));
+    }
+
+    if let Some(current) = Sinners.current_at(now) {
+        metrics.levels("dq10_sinners_level", "Sinners in the Abyss level per boss", &current.value);
+        metrics.gauge("dq10_sinners_change_seconds", "Seconds until the Sinners in the Abyss levels change", &[], seconds(current.end - now));
+    }
+
+    if let Some(current) = SaintGuardians.current_at(now) {
+        metrics.levels("dq10_saint_guardians_level", "Saint Guardians level per boss", &current.value);
+        metrics.gauge("dq10_saint_guardians_change_seconds", "Seconds until the Saint Guardians levels change", &[], seconds(current.end - now));
+    }
+
+    metrics.0
+}
+
+fn seconds(duration: chrono::Duration) -> f64 {
+    duration.num_milliseconds() as f64 / 1000.0
+}
+
+#[derive(Default)]
+struct Metrics(String);
+
+impl Metrics {
+    fn header(&mut self, name: &str, help: &str) {
+        writeln!(self.0, "# HELP {} {}", name, help).unwrap();
+        writeln!(self.0, "# TYPE {} gauge", name).unwrap();
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let labels: Vec<String> = labels.iter().map(|(k, v)| format!("{}=\"{}\"", k, escape(v))).collect();
+        if labels.is_empty() {
+            writeln!(self.0, "{} {}", name, value).unwrap();
+        } else {
+            writeln!(self.0, "{}{{{}}} {}", name, labels.join(","), value).unwrap();
+        }
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        self.header(name, help);
+        self.sample(name, labels, value);
+    }
+
+    fn levels(&mut self, name: &str, help: &str, levels: &[(Boss, Level)]) {
+        self.header(name, help);
+        for (boss, level) in levels {
+            self.sample(name, &[("boss", boss.name())], level.0 as f64);
+        }
+    }
+}
+
+/// ラベルの値のエスケープ
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use chrono_tz::Asia::Tokyo;
+
+    #[test]
+    fn test_render() {
+        // 起点から36時間4分後。冥翼兵団で、56分後に重滅兵団に変わる
+        let now = Tokyo.with_ymd_and_hms(2025, 12, 11, 18, 4, 0).unwrap().to_utc();
+        let metrics = super::render(now);
+        assert!(metrics.contains("# TYPE dq10_defense_force_troop_index gauge\n"));
+        assert!(metrics.contains("dq10_defense_force_troop_index{id=\"BIRD\",name=\"白雲の冥翼兵団\"} 9\n"));
+        assert!(metrics.contains("dq10_defense_force_change_seconds 3360\n"));
+        assert!(metrics.contains("dq10_sinners_level{boss=\"絶念のアウルモッド\"} "));
+        assert_eq!(metrics.matches("dq10_saint_guardians_level{boss=").count(), 6);
+        assert!(metrics.contains("dq10_panigarm_boss_index{id="));
+        assert!(metrics.contains("dq10_ast_hour "));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(super::escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
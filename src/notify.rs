@@ -0,0 +1,116 @@
+use std::io::{self, Write};
+use std::process::Command;
+use chrono::{DateTime, Duration, Local, Utc};
+use crate::clock;
+
+/// 通知する内容
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub message: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// 通知の送り先
+pub trait Sink {
+    fn send(&self, notification: &Notification) -> Result<(), String>;
+}
+
+/// 端末のベルを鳴らし、標準出力にメッセージを表示する
+pub struct Bell;
+
+impl Sink for Bell {
+    fn send(&self, notification: &Notification) -> Result<(), String> {
+        let mut stdout = io::stdout();
+        writeln!(stdout, "\x07{} {}", clock::now().with_timezone(&Local).format("%m/%d %H:%M:%S"), notification.message)
+            .and_then(|_| stdout.flush())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// notify-sendコマンドでデスクトップに通知する
+pub struct NotifySend;
+
+impl Sink for NotifySend {
+    fn send(&self, notification: &Notification) -> Result<(), String> {
+        let mut command = Command::new("notify-send");
+        command.arg(&notification.title).arg(&notification.message);
+        run(command, "notify-send")
+    }
+}
+
+/// 任意のコマンドをシェルで実行する。通知の内容は環境変数で渡す
+///
+/// DQ10_TITLE, DQ10_MESSAGE, DQ10_START, DQ10_END (RFC 3339)
+pub struct ShellCommand(pub String);
+
+impl Sink for ShellCommand {
+    fn send(&self, notification: &Notification) -> Result<(), String> {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(&self.0)
+            .env("DQ10_TITLE", &notification.title)
+            .env("DQ10_MESSAGE", &notification.message)
+            .env("DQ10_START", notification.start.with_timezone(&Local).to_rfc3339())
+            .env("DQ10_END", notification.end.with_timezone(&Local).to_rfc3339());
+        run(command, &self.0)
+    }
+}
+
+fn run(mut command: Command, name: &str) -> Result<(), String> {
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{} failed: {}", name, status)),
+        Err(e) => Err(format!("{} failed: {}", name, e)),
+    }
+}
+
+/// 開始のbefore前に通知する時刻。すでに通知時刻を過ぎていれば今すぐ、開始済みならNone
+pub fn alert_at(start: DateTime<Utc>, before: Duration, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if start <= now {
+        None
+    } else {
+        // 長すぎるbeforeで日時が表せなければすぐに通知する
+        Some(start.checked_sub_signed(before).map_or(now, |at| at.max(now)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use super::{Notification, ShellCommand, Sink};
+
+    fn notification() -> Notification {
+        Notification {
+            title: "金神の遺宝兵団".to_string(),
+            message: "金神の遺宝兵団が5分後に始まります".to_string(),
+            start: Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap(),
+            end: Utc.with_ymd_and_hms(2025, 12, 9, 22, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_shell_command() {
+        let sink = ShellCommand("test \"$DQ10_TITLE\" = 金神の遺宝兵団 && test -n \"$DQ10_START\"".to_string());
+        assert_eq!(sink.send(&notification()), Ok(()));
+    }
+
+    #[test]
+    fn test_shell_command_failure() {
+        let sink = ShellCommand("exit 3".to_string());
+        assert!(sink.send(&notification()).is_err());
+    }
+
+    #[test]
+    fn test_alert_at() {
+        let start = Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap();
+        let before = Duration::minutes(5);
+        let now = start - Duration::hours(1);
+        assert_eq!(super::alert_at(start, before, now), Some(start - before));
+        // 通知時刻を過ぎていればすぐに通知する
+        let now = start - Duration::minutes(2);
+        assert_eq!(super::alert_at(start, before, now), Some(now));
+        assert_eq!(super::alert_at(start, before, start), None);
+        assert_eq!(super::alert_at(start, Duration::milliseconds(i64::MAX), now), Some(now));
+    }
+}
@@ -1,74 +1,108 @@
-use chrono::{DateTime, Local, TimeZone, NaiveDateTime, Utc};
-use chrono_tz::Asia::Tokyo;
-
-#[derive(PartialEq, Eq)]
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use crate::clock::Clock;
+use crate::data;
+use crate::error::{Error, Result};
+use crate::rotation::{Occurrence, Rotation};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize)]
 pub struct Boss {
+   pub id: &'static str,
    pub name: &'static str
 }
-const BIRD: Boss = Boss { name: "源世鳥アルマナ" };
-const DRAGON: Boss = Boss { name: "じげんりゅう" };
-const CATCHER: Boss = Boss { name: "パニガキャッチャー" };
-const FRUITS: Boss = Boss { name: "源世果フルポティ" };
-const CORVUS: Boss = Boss { name: "堕天使エルギオス" };
-const PRISON: Boss = Boss { name: "源世妃フォルダイナ" };
-const STAR: Boss = Boss { name: "魔妖星プルタヌス" };
-const IRON: Boss = Boss { name: "鉄巨兵ダイダルモス" };
-
-pub struct Event {
-    pub boss: &'static Boss,
-    pub started_at: DateTime<Local>,
+
+impl Boss {
+    fn from_data(boss: &'static data::BossData) -> Self {
+        Boss { id: &boss.id, name: &boss.name }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(bound(serialize = ""))]
+pub struct Event<Tz: TimeZone> {
+    pub boss: Boss,
+    pub started_at: DateTime<Tz>,
+}
+
+/// あるボスが出現する期間
+#[derive(Serialize)]
+#[serde(bound(serialize = ""))]
+pub struct Window<Tz: TimeZone> {
+    pub boss: Boss,
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+}
+
+/// ボスを名前で探す。探し方はdata::find_by_nameと同じ
+pub fn find_boss(query: &str) -> Result<Boss> {
+    data::find_by_name(&data::try_get()?.panigarm.bosses, "boss", query).map(Boss::from_data)
 }
 
-// v7.3, 2025-01-29 6:00を起点とするスケジュール。三日周期でボスが変わる。
-// 順番は変わらない想定
-const SEQUENCE: [&Boss; 8] = [
-    &BIRD,
-    &DRAGON,
-    &PRISON,
-    &IRON,
-    &CATCHER,
-    &FRUITS,
-    &STAR,
-    &CORVUS,
-];
-
-pub fn get_current_event() -> Event {
-    get_event(Utc::now())
+/// 指定された日時以降にボスが出現する期間をcount件取得する。指定日時に出現中の期間も含む
+pub fn find_windows<Tz: TimeZone>(boss: Boss, dt: DateTime<Utc>, count: usize, tz: Tz) -> Vec<Window<Tz>> {
+    Panigarm.find(&boss, dt, count).into_iter()
+        .map(|o| Window {
+            boss,
+            start: o.start.with_timezone(&tz),
+            end: o.end.with_timezone(&tz),
+        })
+        .collect()
 }
 
-pub fn get_event(dt: DateTime<Utc>) -> Event {
-    let base_point = get_base_point();
-    let calc_point = dt.naive_utc();
-    let duration = calc_point - base_point;
-    let period = (duration.num_days() / 3) as usize;
-    let index = period % SEQUENCE.len();
-    let boss = SEQUENCE[index];
+pub fn get_current_event<Tz: TimeZone>(clock: &impl Clock, tz: Tz) -> Result<Event<Tz>> {
+    get_event(clock.now(), tz)
+}
 
-    let utc_started_at = base_point + chrono::Duration::days(period as i64 * 3);
-    let started_at = Local.from_utc_datetime(&utc_started_at);
+/// 指定時刻に有効なEraのローテーションからボスを決める。最初のEraより前はBeforeBasePoint
+pub fn get_event<Tz: TimeZone>(dt: DateTime<Utc>, tz: Tz) -> Result<Event<Tz>> {
+    let panigarm = &data::try_get()?.panigarm;
+    let slot = panigarm.eras.try_slot_at(dt.naive_utc())?;
+    // 周期に含まれるボスはデータ読み込み時に検証済みだが、panicはしない
+    let boss = panigarm.boss(slot.entry()).map(Boss::from_data)
+        .ok_or_else(|| Error::InvalidData(format!("panigarm: unknown boss {}", slot.entry())))?;
+    let started_at = tz.from_utc_datetime(&slot.start);
 
-    Event {
+    Ok(Event {
         boss,
         started_at,
-    }
+    })
 }
 
-pub fn get_cycle(dt: DateTime<Utc>) -> Vec<Event> {
+/// 指定時刻から一周期分のイベントを取得する。Eraが切り替わる場合は新しいEraのボスが続く
+pub fn get_cycle<Tz: TimeZone>(dt: DateTime<Utc>, tz: Tz) -> Result<Vec<Event<Tz>>> {
+    let eras = &data::try_get()?.panigarm.eras;
     let mut result = Vec::new();
-    for i in 0..SEQUENCE.len() {
-        let boss = get_event(dt + chrono::Duration::days(i as i64 * 3));
-        result.push(boss);
+    let mut calc_point = dt.naive_utc();
+    eras.try_slot_at(calc_point)?;
+    while let Some(slot) = eras.slot_at(calc_point) {
+        if result.len() >= slot.era.cycle.len() {
+            break;
+        }
+        result.push(get_event(Utc.from_utc_datetime(&calc_point), tz.clone())?);
+        calc_point = slot.end;
     }
-    result
+    Ok(result)
 }
 
-pub fn get_next_cycle() -> Vec<Event> {
-    get_cycle(Utc::now())
+pub fn get_next_cycle<Tz: TimeZone>(clock: &impl Clock, tz: Tz) -> Result<Vec<Event<Tz>>> {
+    get_cycle(clock.now(), tz)
 }
 
-fn get_base_point() -> NaiveDateTime {
-    // 2025-01-29 06:00:00 JST (v7.3 公開日)
-    Tokyo.with_ymd_and_hms(2025, 1, 29, 6, 0, 0).unwrap().naive_utc()
+/// パニガルムのローテーション
+pub struct Panigarm;
+
+impl Rotation for Panigarm {
+    type Item = Boss;
+
+    fn current_at(&self, dt: DateTime<Utc>) -> Option<Occurrence<Boss>> {
+        let panigarm = &data::try_get().ok()?.panigarm;
+        let slot = panigarm.eras.slot_at(dt.naive_utc())?;
+        Some(Occurrence {
+            value: Boss::from_data(panigarm.boss(slot.entry())?),
+            start: Utc.from_utc_datetime(&slot.start),
+            end: Utc.from_utc_datetime(&slot.end),
+        })
+    }
 }
 
 
@@ -76,38 +110,91 @@ fn get_base_point() -> NaiveDateTime {
 mod tests {
     use chrono::prelude::*;
 
+    #[test]
+    fn test_get_current_event_with_fixed_clock() {
+        let dt = chrono::FixedOffset::east_opt(9 * 3600).unwrap().with_ymd_and_hms(2025, 2, 1, 6, 0, 0).single().unwrap();
+        let clock = crate::clock::FixedClock(dt.to_utc());
+        assert_eq!(super::get_current_event(&clock, chrono::Utc).unwrap().boss.name, "じげんりゅう");
+        assert_eq!(super::get_next_cycle(&clock, chrono::Utc).unwrap()[0].boss.name, "じげんりゅう");
+    }
+
     #[test]
     fn test_get_boss() {
         // 基準日の6時から3日周期でボスが変わる
         let tz = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
         let dt = tz.with_ymd_and_hms(2025, 1, 29, 13, 0, 0).single().unwrap();
-        let event = super::get_event(dt.to_utc());
+        let event = super::get_event(dt.to_utc(), tz).unwrap();
         assert_eq!(event.boss.name, "源世鳥アルマナ");
         assert_eq!(event.started_at, tz.with_ymd_and_hms(2025, 1, 29, 6, 0, 0).single().unwrap());
 
         let tz = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
         let dt = tz.with_ymd_and_hms(2025, 2, 1, 5, 59, 59).single().unwrap();
-        let event = super::get_event(dt.to_utc());
+        let event = super::get_event(dt.to_utc(), tz).unwrap();
         assert_eq!(event.boss.name, "源世鳥アルマナ");
         assert_eq!(event.started_at, tz.with_ymd_and_hms(2025, 1, 29, 6, 0, 0).single().unwrap());
 
         // 次のボスの切り替わり時間
         let tz = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
         let dt = tz.with_ymd_and_hms(2025, 2, 1, 6, 0, 0).single().unwrap();
-        let event = super::get_event(dt.to_utc());
+        let event = super::get_event(dt.to_utc(), tz).unwrap();
         assert_eq!(event.boss.name, "じげんりゅう");
         assert_eq!(event.started_at, tz.with_ymd_and_hms(2025, 2, 1, 6, 0, 0).single().unwrap());
 
         // 繰り返しテスト, 24日後に同じボスが出現
         let tz = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
         let dt = tz.with_ymd_and_hms(2025, 2, 19, 6, 0, 0).single().unwrap();
-        let event = super::get_event(dt.to_utc());
+        let event = super::get_event(dt.to_utc(), tz).unwrap();
         assert_eq!(event.boss.name, "堕天使エルギオス");
         assert_eq!(event.started_at, tz.with_ymd_and_hms(2025, 2, 19, 6, 0, 0).single().unwrap());
 
         let dt = dt + chrono::Duration::days(24);
-        let event = super::get_event(dt.to_utc());
+        let event = super::get_event(dt.to_utc(), tz).unwrap();
         assert_eq!(event.boss.name, "堕天使エルギオス");
         assert_eq!(event.started_at, tz.with_ymd_and_hms(2025, 3, 15, 6, 0, 0).single().unwrap());
     }
+
+    #[test]
+    fn test_get_event_before_first_era_is_error() {
+        let tz = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        let dt = tz.with_ymd_and_hms(2025, 1, 29, 5, 59, 59).single().unwrap();
+        assert!(matches!(super::get_event(dt.to_utc(), tz), Err(crate::error::Error::BeforeBasePoint(_))));
+        assert!(super::get_cycle(dt.to_utc(), tz).is_err());
+    }
+
+    #[test]
+    fn test_get_cycle() {
+        let tz = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        let dt = tz.with_ymd_and_hms(2025, 2, 2, 0, 0, 0).single().unwrap();
+        let cycle = super::get_cycle(dt.to_utc(), tz).unwrap();
+        assert_eq!(cycle.len(), 8);
+        assert_eq!(cycle[0].boss.name, "じげんりゅう");
+        assert_eq!(cycle[0].started_at, tz.with_ymd_and_hms(2025, 2, 1, 6, 0, 0).single().unwrap());
+        assert_eq!(cycle[7].boss.name, "源世鳥アルマナ");
+        assert_eq!(cycle[7].started_at, tz.with_ymd_and_hms(2025, 2, 22, 6, 0, 0).single().unwrap());
+    }
+
+    #[test]
+    fn test_find_boss() {
+        assert_eq!(super::find_boss("堕天使エルギオス").unwrap().id, "CORVUS");
+        assert_eq!(super::find_boss("エルギオス").unwrap().id, "CORVUS");
+        assert_eq!(super::find_boss("corvus").unwrap().id, "CORVUS");
+        assert_eq!(super::find_boss("フルポティ").unwrap().id, "FRUITS");
+        assert!(super::find_boss("源世").unwrap_err().to_string().starts_with("ambiguous boss"));
+        assert!(super::find_boss("存在しない").unwrap_err().to_string().starts_with("unknown boss"));
+        assert!(super::find_boss(" ").is_err());
+    }
+
+    #[test]
+    fn test_find_windows() {
+        // エルギオスは2025-02-19 6:00から3日間、以降24日ごとに出現する
+        let tz = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        let boss = super::find_boss("corvus").unwrap();
+        let dt = tz.with_ymd_and_hms(2025, 2, 20, 12, 0, 0).single().unwrap();
+        let windows = super::find_windows(boss, dt.to_utc(), 3, tz);
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[0].start, tz.with_ymd_and_hms(2025, 2, 19, 6, 0, 0).single().unwrap());
+        assert_eq!(windows[0].end, tz.with_ymd_and_hms(2025, 2, 22, 6, 0, 0).single().unwrap());
+        assert_eq!(windows[1].start, tz.with_ymd_and_hms(2025, 3, 15, 6, 0, 0).single().unwrap());
+        assert_eq!(windows[2].start, tz.with_ymd_and_hms(2025, 4, 8, 6, 0, 0).single().unwrap());
+    }
 }
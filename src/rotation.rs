@@ -0,0 +1,124 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+/// ローテーションの一区間。startからendの直前までvalueが有効
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Occurrence<T> {
+    pub value: T,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// 起点と周期によって順番に切り替わるコンテンツ
+pub trait Rotation {
+    type Item;
+
+    /// 指定時刻に有効な区間。データのない時刻はNone
+    fn current_at(&self, dt: DateTime<Utc>) -> Option<Occurrence<Self::Item>>;
+
+    /// 指定時刻の次に切り替わる時刻
+    fn next_change_after(&self, dt: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.current_at(dt).map(|o| o.end)
+    }
+
+    /// 指定時刻に有効な区間から順に、以降の区間を列挙する
+    fn upcoming(&self, dt: DateTime<Utc>) -> Upcoming<'_, Self> where Self: Sized {
+        Upcoming { rotation: self, next: Some(dt) }
+    }
+
+    /// 指定時刻以降でvalueが有効な区間をcount件取得する。指定時刻に有効な区間も含む
+    fn find(&self, value: &Self::Item, dt: DateTime<Utc>, count: usize) -> Vec<Occurrence<Self::Item>>
+    where Self: Sized, Self::Item: PartialEq {
+        let limit = dt.checked_add_signed(Duration::days(FIND_LIMIT_DAYS)).unwrap_or(DateTime::<Utc>::MAX_UTC);
+        self.upcoming(dt)
+            .take_while(|o| o.start < limit)
+            .filter(|o| o.value == *value)
+            .take(count)
+            .collect()
+    }
+}
+
+// 周期に含まれない値を探し続けないように、一年先までで打ち切る
+const FIND_LIMIT_DAYS: i64 = 366;
+
+pub struct Upcoming<'a, R: Rotation> {
+    rotation: &'a R,
+    next: Option<DateTime<Utc>>,
+}
+
+impl<R: Rotation> Iterator for Upcoming<'_, R> {
+    type Item = Occurrence<R::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let occurrence = self.rotation.current_at(self.next?);
+        self.next = occurrence.as_ref().map(|o| o.end);
+        occurrence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::prelude::*;
+    use super::Rotation;
+    use crate::defense_force::DefenseForce;
+    use crate::panigarm::Panigarm;
+    use crate::saint_guardians::SaintGuardians;
+    use crate::sinners::Sinners;
+
+    /// 区間が隙間なく続くこと
+    fn assert_contiguous<R: Rotation>(rotation: R, dt: DateTime<Utc>) {
+        let occurrences: Vec<_> = rotation.upcoming(dt).take(10).collect();
+        assert_eq!(occurrences.len(), 10);
+        assert!(occurrences[0].start <= dt && dt < occurrences[0].end);
+        for w in occurrences.windows(2) {
+            assert_eq!(w[0].end, w[1].start);
+        }
+        assert_eq!(rotation.next_change_after(dt), Some(occurrences[0].end));
+    }
+
+    #[test]
+    fn test_upcoming_is_contiguous() {
+        let dt = Utc.with_ymd_and_hms(2025, 12, 20, 3, 30, 0).unwrap();
+        assert_contiguous(DefenseForce, dt);
+        assert_contiguous(Panigarm, dt);
+        assert_contiguous(Sinners, dt);
+        assert_contiguous(SaintGuardians, dt);
+    }
+
+    #[test]
+    fn test_defense_force_occurrence() {
+        // JSTの2025-12-10 6:00から金神、7:00から暴賊
+        let dt = Utc.with_ymd_and_hms(2025, 12, 9, 21, 30, 0).unwrap();
+        let occurrence = DefenseForce.current_at(dt).unwrap();
+        assert_eq!(occurrence.value.name(), "金神の遺宝兵団");
+        assert_eq!(occurrence.start, Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap());
+        assert_eq!(occurrence.end, Utc.with_ymd_and_hms(2025, 12, 9, 22, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_panigarm_occurrence() {
+        let dt = Utc.with_ymd_and_hms(2025, 1, 30, 0, 0, 0).unwrap();
+        let occurrence = Panigarm.current_at(dt).unwrap();
+        assert_eq!(occurrence.value.name, "源世鳥アルマナ");
+        assert_eq!(occurrence.start, Utc.with_ymd_and_hms(2025, 1, 28, 21, 0, 0).unwrap());
+        assert_eq!(occurrence.end, Utc.with_ymd_and_hms(2025, 1, 31, 21, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_find() {
+        let dt = Utc.with_ymd_and_hms(2025, 12, 9, 21, 30, 0).unwrap();
+        let gold = DefenseForce.current_at(dt).unwrap().value;
+        let found = DefenseForce.find(&gold, dt, 3);
+        assert_eq!(found.len(), 3);
+        assert!(found.iter().all(|o| o.value == gold));
+        assert!(found[0].start <= dt);
+    }
+
+    #[test]
+    fn test_no_occurrence_before_data() {
+        let dt = Utc.with_ymd_and_hms(2018, 1, 1, 0, 0, 0).unwrap();
+        assert!(DefenseForce.current_at(dt).is_none());
+        assert!(Panigarm.upcoming(dt).next().is_none());
+        assert!(Sinners.next_change_after(dt).is_none());
+    }
+}
@@ -1,51 +1,102 @@
-use chrono::{Local, DateTime, TimeZone};
-use serde_json::{json, Value};
+use chrono::{DateTime, TimeZone, Utc};
+use crate::clock::Clock;
+use crate::data;
+use crate::error;
+use crate::levels::{self, LevelsContent};
 
-pub fn get_levels(now: DateTime<Local>) -> Option<Value> {
-    let base_point_opt = Local.with_ymd_and_hms(2018, 4, 20, 6, 0, 0);
+pub use crate::levels::Boss;
 
+pub type Levels<Tz> = levels::Levels<Tz>;
 
-    base_point_opt.single().and_then( |base_point| {
-        if base_point > now {
-            return None;
-        }
+/// 聖守護者のレベルのローテーション
+pub struct SaintGuardians;
 
-        let d = (now - base_point).num_days();
+impl LevelsContent for SaintGuardians {
+    const TITLE: &'static str = "聖守護者";
+    const KIND: &'static str = "saint-guardians";
 
-        let d0 = d % 3 + 1;
-        let d2 = (d + 2) % 3 + 1;
-        let d1 = (d + 1) % 3 + 1;
+    fn data(data: &data::Data) -> &data::LevelsData {
+        &data.saint_guardians
+    }
+}
 
-        let json = json!({
-            "レギルラッゾたち": d0,
-            "スコルパイド": d2,
-            "ジェルザーク": d1,
-            "ガルドドン": d1,
-            "デルメゼ": d2,
-            "バラシュナ": d0
-        });
+pub fn get_current_levels<Tz: TimeZone>(clock: &impl Clock, tz: Tz) -> error::Result<Levels<Tz>> {
+    get_levels(clock.now(), tz)
+}
 
-        Some(json)
-    })
+/// レベルは日本時間の6:00に切り替わる。実行環境のタイムゾーンによらず、期間はtzで表す
+pub fn get_levels<Tz: TimeZone>(now: DateTime<Utc>, tz: Tz) -> error::Result<Levels<Tz>> {
+    levels::get_levels::<SaintGuardians, Tz>(now, tz)
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::prelude::*;
+    use chrono::{TimeZone, FixedOffset, Utc};
+    use chrono_tz::America::New_York;
+    use chrono_tz::Asia::{Tokyo, Singapore};
+    use crate::levels::Level;
+
     #[test]
     fn test_get_level() {
-        let e = super::get_levels(Local.with_ymd_and_hms(2019, 7, 5, 12, 0, 0).single().unwrap()).unwrap();
-        assert_eq!(e.get("レギルラッゾたち").unwrap(), 1);
-        assert_eq!(e.get("スコルパイド").unwrap(), 3);
-        assert_eq!(e.get("デルメゼ").unwrap(), 3);
-        assert_eq!(e.get("ジェルザーク").unwrap(), 2);
-        assert_eq!(e.get("ガルドドン").unwrap(), 2);
-
-        let e = super::get_levels(Local.with_ymd_and_hms(2020, 11, 18, 6, 0, 0).single().unwrap()).unwrap();
-        assert_eq!(e.get("レギルラッゾたち").unwrap(), 2);
-        assert_eq!(e.get("スコルパイド").unwrap(), 1);
-        assert_eq!(e.get("デルメゼ").unwrap(), 1);
-        assert_eq!(e.get("ジェルザーク").unwrap(), 3);
-        assert_eq!(e.get("ガルドドン").unwrap(), 3);
+        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
+
+        let dt = jst.with_ymd_and_hms(2019, 7, 5, 12, 0, 0).single().unwrap();
+        let e = super::get_levels(dt.to_utc(), jst).unwrap();
+        assert_eq!(e.level("REGILLAZZO"), Some(Level(1)));
+        assert_eq!(e.level("SCORPIDE"), Some(Level(3)));
+        assert_eq!(e.level("DELMEZE"), Some(Level(3)));
+        assert_eq!(e.level("JELZARK"), Some(Level(2)));
+        assert_eq!(e.level("GARDODON"), Some(Level(2)));
+        assert_eq!(e.term.from.to_rfc3339(), "2019-07-05T06:00:00+09:00");
+        assert_eq!(e.term.to.to_rfc3339(), "2019-07-06T05:59:59+09:00");
+
+        let dt = Tokyo.with_ymd_and_hms(2020, 11, 18, 6, 0, 0).single().unwrap();
+        let e = super::get_levels(dt.to_utc(), Tokyo).unwrap();
+        assert_eq!(e.level("REGILLAZZO"), Some(Level(2)));
+        assert_eq!(e.level("SCORPIDE"), Some(Level(1)));
+        assert_eq!(e.level("DELMEZE"), Some(Level(1)));
+        assert_eq!(e.level("JELZARK"), Some(Level(3)));
+        assert_eq!(e.level("GARDODON"), Some(Level(3)));
+    }
+
+    #[test]
+    fn test_get_levels_utc() {
+        // UTCの2019/7/4 21:00:00はJSTの2019/7/5 6:00:00
+        let dt = Utc.with_ymd_and_hms(2019, 7, 4, 21, 0, 0).single().unwrap();
+        let e = super::get_levels(dt, Utc).unwrap();
+        assert_eq!(e.level("REGILLAZZO"), Some(Level(1)));
+        assert_eq!(e.term.from.to_rfc3339(), "2019-07-04T21:00:00+00:00");
+        assert_eq!(e.term.to.to_rfc3339(), "2019-07-05T20:59:59+00:00");
+
+        // 1秒前は前の日のレベル
+        let e = super::get_levels(dt - chrono::Duration::seconds(1), Utc).unwrap();
+        assert_eq!(e.level("REGILLAZZO"), Some(Level(3)));
+        assert_eq!(e.term.to.to_rfc3339(), "2019-07-04T20:59:59+00:00");
+    }
+
+    #[test]
+    fn test_get_levels_sst() {
+        // SSTの2019/7/5 5:00:00はJSTの2019/7/5 6:00:00
+        let dt = Singapore.with_ymd_and_hms(2019, 7, 5, 5, 0, 0).single().unwrap();
+        let e = super::get_levels(dt.to_utc(), Singapore).unwrap();
+        assert_eq!(e.level("REGILLAZZO"), Some(Level(1)));
+        assert_eq!(e.term.from.to_rfc3339(), "2019-07-05T05:00:00+08:00");
+        assert_eq!(e.term.to.to_rfc3339(), "2019-07-06T04:59:59+08:00");
+    }
+
+    #[test]
+    fn test_get_levels_new_york() {
+        // EDTの2019/7/4 17:00:00はJSTの2019/7/5 6:00:00
+        let dt = New_York.with_ymd_and_hms(2019, 7, 4, 17, 0, 0).single().unwrap();
+        let e = super::get_levels(dt.to_utc(), New_York).unwrap();
+        assert_eq!(e.level("REGILLAZZO"), Some(Level(1)));
+        assert_eq!(e.term.from.to_rfc3339(), "2019-07-04T17:00:00-04:00");
+        assert_eq!(e.term.to.to_rfc3339(), "2019-07-05T16:59:59-04:00");
+    }
+
+    #[test]
+    fn test_get_levels_before_base_point_is_error() {
+        let dt = Tokyo.with_ymd_and_hms(2018, 4, 20, 5, 59, 59).single().unwrap();
+        assert!(matches!(super::get_levels(dt.to_utc(), Tokyo), Err(crate::error::Error::BeforeBasePoint(_))));
     }
 }
@@ -0,0 +1,362 @@
+use std::panic;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::json;
+use crate::ast;
+use crate::clock;
+use crate::defense_force::{schedule, state, troop, DefenseForce};
+use crate::error::{self, Error};
+use crate::ical;
+use crate::metrics;
+use crate::panigarm::{self, Panigarm};
+use crate::saint_guardians;
+use crate::sinners;
+use crate::zone::Zone;
+
+// 一度に返すスケジュールの上限。一か月分
+const MAX_COUNT: usize = 24 * 31;
+// カレンダーに含める期間の上限
+const MAX_DAYS: i64 = 366;
+// 受け付ける時刻の範囲。0000-01-01T00:00:00Zから9999-12-31T23:59:59ZまでのUnix時刻
+const MIN_TIME: i64 = -62167219200;
+const MAX_TIME: i64 = 253402300799;
+
+/// HTTPのレスポンス
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn json<T: Serialize>(data: &T) -> Response {
+        match serde_json::to_string(data) {
+            Ok(body) => Response { status: 200, content_type: "application/json", body },
+            Err(e) => Response::error(500, &e.to_string()),
+        }
+    }
+
+    fn calendar(name: &str, events: &[ical::VEvent]) -> Response {
+        Response { status: 200, content_type: "text/calendar; charset=utf-8", body: ical::to_ics(name, events, clock::now()) }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response { status, content_type: "application/json", body: json!({ "error": message }).to_string() }
+    }
+
+    /// データのない時刻は404、それ以外のエラーは400にする
+    fn json_or_error<T: Serialize>(data: error::Result<T>) -> Response {
+        match data {
+            Ok(data) => Response::json(&data),
+            Err(e @ Error::BeforeBasePoint(_)) => Response::error(404, &e.to_string()),
+            Err(e) => Response::error(400, &e.to_string()),
+        }
+    }
+}
+
+/// リクエストのパスとクエリからレスポンスを作る。時刻はtzか、クエリのtzのタイムゾーンで返す
+pub fn handle(method: &str, url: &str, tz: Zone) -> Response {
+    if method != "GET" {
+        return Response::error(405, "method not allowed");
+    }
+
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let params = Params::parse(query);
+    match params.zone(tz).and_then(|tz| route(path.trim_end_matches('/'), &params, tz)) {
+        Ok(response) => response,
+        Err(message) => Response::error(400, &message),
+    }
+}
+
+fn route(path: &str, params: &Params, tz: Zone) -> Result<Response, String> {
+    let response = match path {
+        "/ast" => Response::json_or_error(ast::from_datetime(params.time("at")?)),
+        "/defense-force/state" => Response::json_or_error(state::get_state(params.time("at")?, tz)),
+        "/defense-force/window" => Response::json_or_error(schedule::get_window(params.time("at")?, tz)),
+        "/defense-force/schedule" => {
+            let count = params.count()?;
+            Response::json_or_error(schedule::get_schedule_in(params.time("from")?, count, tz))
+        }
+        "/panigarm/cycle" => Response::json_or_error(panigarm::get_cycle(params.time("at")?, tz)),
+        "/sinners" => Response::json_or_error(sinners::get_levels(params.time("at")?, tz)),
+        "/saint-guardians" => Response::json_or_error(saint_guardians::get_levels(params.time("at")?, tz)),
+        "/calendar/defense-force.ics" => defense_force_calendar(params)?,
+        "/calendar/panigarm.ics" => panigarm_calendar(params)?,
+        "/metrics" => Response { status: 200, content_type: metrics::CONTENT_TYPE, body: metrics::render(clock::now()) },
+        _ => Response::error(404, "not found"),
+    };
+    Ok(response)
+}
+
+/// 防衛軍のカレンダー。troopsで兵団を絞り込む。既定は7日分
+fn defense_force_calendar(params: &Params) -> Result<Response, String> {
+    let troops = params.list("troops").iter().map(|query| troop::find_troop(query)).collect::<error::Result<Vec<_>>>().map_err(|e| e.to_string())?;
+    let from = params.time("from")?;
+    let to = from.checked_add_signed(Duration::days(params.days(7)?)).unwrap_or(DateTime::<Utc>::MAX_UTC);
+    let events = ical::from_rotation(&DefenseForce, "defense-force", from, to, |troop| {
+        (troops.is_empty() || troops.contains(troop)).then(|| troop.name().to_string())
+    });
+    Ok(Response::calendar(&calendar_name("防衛軍", troops.iter().map(|t| t.name())), &events))
+}
+
+/// パニガルムのカレンダー。bossesでボスを絞り込む。既定は48日分
+fn panigarm_calendar(params: &Params) -> Result<Response, String> {
+    let bosses = params.list("bosses").iter().map(|query| panigarm::find_boss(query)).collect::<error::Result<Vec<_>>>().map_err(|e| e.to_string())?;
+    let from = params.time("from")?;
+    let to = from.checked_add_signed(Duration::days(params.days(48)?)).unwrap_or(DateTime::<Utc>::MAX_UTC);
+    let events = ical::from_rotation(&Panigarm, "panigarm", from, to, |boss| {
+        (bosses.is_empty() || bosses.contains(boss)).then(|| boss.name.to_string())
+    });
+    Ok(Response::calendar(&calendar_name("パニガルム", bosses.iter().map(|b| b.name)), &events))
+}
+
+fn calendar_name<'a>(title: &str, names: impl Iterator<Item = &'a str>) -> String {
+    let names: Vec<&str> = names.collect();
+    if names.is_empty() {
+        title.to_string()
+    } else {
+        format!("{} ({})", title, names.join(", "))
+    }
+}
+
+/// クエリパラメーター
+struct Params(Vec<(String, String)>);
+
+impl Params {
+    fn parse(query: &str) -> Params {
+        Params(query.split('&').filter(|pair| !pair.is_empty()).map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        }).collect())
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// 省略時は現在時刻。RFC 3339で書ける0年から9999年までに限る
+    fn time(&self, key: &str) -> Result<DateTime<Utc>, String> {
+        let dt = match self.get(key) {
+            None | Some("") => return Ok(clock::now()),
+            Some(value) => clock::parse_time(value).map_err(|e| e.to_string())?,
+        };
+        if !(MIN_TIME..=MAX_TIME).contains(&dt.timestamp()) {
+            return Err(format!("{} must be between 0000-01-01T00:00:00Z and 9999-12-31T23:59:59Z", key));
+        }
+        Ok(dt)
+    }
+
+    /// 省略時はサーバーのタイムゾーン
+    fn zone(&self, default: Zone) -> Result<Zone, String> {
+        match self.get("tz") {
+            None | Some("") => Ok(default),
+            Some(value) => value.parse(),
+        }
+    }
+
+    /// カンマ区切りの値。省略時は空
+    fn list(&self, key: &str) -> Vec<&str> {
+        self.get(key).map(|v| v.split(',').map(str::trim).filter(|s| !s.is_empty()).collect()).unwrap_or_default()
+    }
+
+    fn days(&self, default: i64) -> Result<i64, String> {
+        match self.get("days") {
+            None | Some("") => Ok(default),
+            Some(value) => match value.parse() {
+                Ok(days) if (1..=MAX_DAYS).contains(&days) => Ok(days),
+                _ => Err(format!("days must be between 1 and {}", MAX_DAYS)),
+            }
+        }
+    }
+
+    fn count(&self) -> Result<usize, String> {
+        match self.get("count") {
+            None | Some("") => Ok(24),
+            Some(value) => match value.parse() {
+                Ok(count) if (1..=MAX_COUNT).contains(&count) => Ok(count),
+                _ => Err(format!("count must be between 1 and {}", MAX_COUNT)),
+            }
+        }
+    }
+}
+
+/// %XXをデコードする。RFC 3339の時差の"+"をそのまま書けるように、"+"は空白にしない
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// 指定したアドレスでリクエストを待ち受け続ける
+pub fn serve(addr: &str, tz: Zone) -> error::Result<()> {
+    let server = tiny_http::Server::http(addr).map_err(|e| Error::Io(format!("{}: {}", addr, e)))?;
+    for request in server.incoming_requests() {
+        // handleはエラーをレスポンスにして返す。それでも残った不具合でサーバーごと止まらないように、最後の手段としてpanicは500にする
+        let (method, url) = (request.method().to_string(), request.url().to_string());
+        let response = panic::catch_unwind(|| handle(&method, &url, tz))
+            .unwrap_or_else(|_| Response::error(500, "internal server error"));
+        let mut http_response = tiny_http::Response::from_string(response.body)
+            .with_status_code(response.status);
+        if let Ok(header) = tiny_http::Header::from_bytes("Content-Type", response.content_type) {
+            http_response.add_header(header);
+        }
+        if let Err(e) = request.respond(http_response) {
+            eprintln!("{}", e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::Asia::Tokyo;
+    use serde_json::Value;
+    use crate::zone::Zone;
+
+    const JST: Zone = Zone::Named(Tokyo);
+
+    fn get(url: &str) -> (u16, Value) {
+        let response = super::handle("GET", url, JST);
+        assert_eq!(response.content_type, "application/json");
+        (response.status, serde_json::from_str(&response.body).unwrap())
+    }
+
+    #[test]
+    fn test_ast() {
+        let (status, body) = get("/ast?at=2018-06-13T10:22:30%2B09:00");
+        assert_eq!(status, 200);
+        assert_eq!(body["time"], "15:30:00");
+    }
+
+    #[test]
+    fn test_defense_force_state() {
+        let (status, body) = get("/defense-force/state?at=2025-12-11T18:04:00+09:00");
+        assert_eq!(status, 200);
+        assert_eq!(body["troop"]["id"], "BIRD");
+        assert_eq!(body["next_in"], 56);
+    }
+
+    #[test]
+    fn test_defense_force_window() {
+        let (status, body) = get("/defense-force/window?at=2025-12-11T18:04:00%2B09:00");
+        assert_eq!(status, 200);
+        assert_eq!(body["troop"]["id"], "BIRD");
+        assert_eq!(body["end"], "2025-12-11T19:00:00+09:00");
+        assert_eq!(body["remaining"], 56);
+    }
+
+    #[test]
+    fn test_time_out_of_range() {
+        let (status, body) = get("/defense-force/state?at=8210266876799000");
+        assert_eq!(status, 400);
+        assert_eq!(body["error"], "at must be between 0000-01-01T00:00:00Z and 9999-12-31T23:59:59Z");
+        assert_eq!(get("/calendar/panigarm.ics?from=253402300800000").0, 400);
+        // 範囲の端でも答えられる
+        for path in ["/ast", "/defense-force/state", "/defense-force/window", "/panigarm/cycle", "/sinners", "/saint-guardians"] {
+            assert_eq!(get(&format!("{}?at=9999-12-31T23:59:59Z", path)).0, 200, "{}", path);
+        }
+        assert_eq!(super::handle("GET", "/calendar/defense-force.ics?from=253402300799000&days=366", JST).status, 200);
+    }
+
+    #[test]
+    fn test_time_zone() {
+        let (_, body) = get("/defense-force/state?at=2025-12-11T18:04:00%2B09:00");
+        assert_eq!(body["changed_at"], "2025-12-11T19:00:00+09:00");
+        let (_, body) = get("/defense-force/state?at=2025-12-11T18:04:00%2B09:00&tz=America/New_York");
+        assert_eq!(body["changed_at"], "2025-12-11T05:00:00-05:00");
+        assert_eq!(get("/defense-force/state?tz=Asia/Nowhere").0, 400);
+    }
+
+    #[test]
+    fn test_defense_force_schedule() {
+        let (status, body) = get("/defense-force/schedule?from=1765314000000&count=3");
+        assert_eq!(status, 200);
+        let ids: Vec<&str> = body.as_array().unwrap().iter().map(|e| e["troop"]["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["GOLD", "GANG", "ALL"]);
+    }
+
+    #[test]
+    fn test_panigarm_cycle() {
+        let (status, body) = get("/panigarm/cycle?at=2025-01-29T06:00:00%2B09:00");
+        assert_eq!(status, 200);
+        assert_eq!(body.as_array().unwrap().len(), 8);
+        assert_eq!(body[0]["boss"]["id"], "BIRD");
+    }
+
+    #[test]
+    fn test_levels() {
+        let (status, body) = get("/sinners?at=2022-05-11T12:00:00Z");
+        assert_eq!(status, 200);
+        assert_eq!(body["level"]["絶念のアウルモッド"], 2);
+
+        let (status, body) = get("/saint-guardians/");
+        assert_eq!(status, 200);
+        assert_eq!(body["level"].as_object().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_defense_force_calendar() {
+        let response = super::handle("GET", "/calendar/defense-force.ics?troops=GOLD,スライム&from=2025-12-10T06:00:00%2B09:00&days=2", JST);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type, "text/calendar; charset=utf-8");
+        assert!(response.body.contains("X-WR-CALNAME:防衛軍 (金神の遺宝兵団\\, 彩虹の粘塊兵団)\r\n"));
+        // 金神は10時間ごと、彩虹は30時間ごと
+        assert_eq!(response.body.matches("SUMMARY:金神の遺宝兵団\r\n").count(), 5);
+        assert_eq!(response.body.matches("SUMMARY:彩虹の粘塊兵団\r\n").count(), 2);
+        assert_eq!(response.body.matches("BEGIN:VEVENT").count(), 7);
+    }
+
+    #[test]
+    fn test_panigarm_calendar() {
+        let response = super::handle("GET", "/calendar/panigarm.ics?bosses=corvus&from=2025-01-29T06:00:00%2B09:00&days=48", JST);
+        assert_eq!(response.status, 200);
+        assert!(response.body.contains("DTSTART;TZID=Asia/Tokyo:20250219T060000\r\n"));
+        assert!(response.body.contains("DTSTART;TZID=Asia/Tokyo:20250315T060000\r\n"));
+        assert_eq!(response.body.matches("BEGIN:VEVENT").count(), 2);
+
+        // 絞り込まなければ全ボス
+        let response = super::handle("GET", "/calendar/panigarm.ics?from=2025-01-29T06:00:00%2B09:00&days=24", JST);
+        assert_eq!(response.body.matches("BEGIN:VEVENT").count(), 8);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let response = super::handle("GET", "/metrics", JST);
+        assert_eq!(response.status, 200);
+        assert!(response.content_type.starts_with("text/plain; version=0.0.4"));
+        assert!(response.body.contains("# TYPE dq10_defense_force_change_seconds gauge\n"));
+    }
+
+    #[test]
+    fn test_calendar_errors() {
+        assert_eq!(super::handle("GET", "/calendar/defense-force.ics?troops=存在しない", JST).status, 400);
+        assert_eq!(super::handle("GET", "/calendar/panigarm.ics?days=1000", JST).status, 400);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(get("/unknown").0, 404);
+        assert_eq!(get("/ast?at=yesterday").0, 400);
+        assert_eq!(get("/defense-force/schedule?count=0").0, 400);
+        // 最初のEraより前
+        let (status, body) = get("/defense-force/state?at=2018-01-01T00:00:00Z");
+        assert_eq!(status, 404);
+        assert!(body["error"].as_str().unwrap().starts_with("no data before "));
+        assert_eq!(super::handle("POST", "/ast", JST).status, 405);
+    }
+}
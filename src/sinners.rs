@@ -1,71 +1,38 @@
-use chrono::{DateTime, Days, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
-use chrono_tz::Asia::Tokyo;
-use serde_json::{json, Value};
+use chrono::{DateTime, TimeZone, Utc};
+use crate::clock::Clock;
+use crate::data;
+use crate::error;
+use crate::levels::{self, LevelsContent};
 
-fn get_base_point() -> NaiveDateTime {
-    Tokyo.with_ymd_and_hms(2022, 5, 11, 6, 0, 0).unwrap().naive_utc()
-}
+pub use crate::levels::Boss;
 
-fn to_term<Tz: TimeZone>(now: NaiveDateTime, tz: Tz) -> (DateTime<Tz>, DateTime<Tz>) {
-    let boundary_time = NaiveTime::from_hms_opt(21, 0, 0).unwrap();
-    let now_time = now.time();
-
-    let delta_one_day = Days::new(1);
-    let (from, to) = if now_time > boundary_time {
-        let from = now.date().and_hms_opt(21, 0, 0).unwrap();
-        let to  = now.date().and_hms_opt(20, 59, 59).unwrap() + delta_one_day;
-        (from, to)
-    } else {
-        let from = now.date().and_hms_opt(21, 0, 0).unwrap() - delta_one_day;
-        let to  = now.date().and_hms_opt(20, 59, 59).unwrap();
-        (from, to)
-    };
-
-    let from_tz = tz.from_utc_datetime(&from);
-    let to_tz = tz.from_utc_datetime(&to);
-
-    (from_tz, to_tz)
-}
+pub type Levels<Tz> = levels::Levels<Tz>;
 
-pub fn get_current_levels() -> Option<Value> {
-    get_levels(Utc::now(), Local)
-}
+/// アビスの咎人のレベルのローテーション
+pub struct Sinners;
 
-pub fn get_levels<Tz: TimeZone>(now: DateTime<Utc>, tz: Tz) -> Option<Value> {
-    let base_point = get_base_point();
-    let given_point = now.naive_utc();
+impl LevelsContent for Sinners {
+    const TITLE: &'static str = "アビスの咎人";
+    const KIND: &'static str = "sinners";
 
-    if given_point < base_point {
-        return None;
+    fn data(data: &data::Data) -> &data::LevelsData {
+        &data.sinners
     }
+}
 
-    let (from, to) = to_term(given_point, tz);
-
-    let d = (given_point - base_point).num_days();
-    let d0 = d % 2 + 1;
-    let d1 = (d + 1) % 2 + 1;
-
-    let json = json!({
-        "level": {
-            "絶念のアウルモッド": d1,
-            "狂禍のフラウソン": d0,
-            "悲愴のウィリーデ" : d1,
-            "燦滅のノクゼリア": d0
-        },
-        "term": {
-            "from": from.to_rfc3339(),
-            "to": to.to_rfc3339()
-        }
-    });
-
-    Some(json)
+pub fn get_current_levels<Tz: TimeZone>(clock: &impl Clock, tz: Tz) -> error::Result<Levels<Tz>> {
+    get_levels(clock.now(), tz)
 }
 
+pub fn get_levels<Tz: TimeZone>(now: DateTime<Utc>, tz: Tz) -> error::Result<Levels<Tz>> {
+    levels::get_levels::<Sinners, Tz>(now, tz)
+}
 
 #[cfg(test)]
 mod tests {
     use chrono::{TimeZone, FixedOffset, Utc};
     use chrono_tz::Asia::{Tokyo, Singapore};
+    use crate::levels::Level;
 
     #[test]
     fn test_get_levels() {
@@ -73,27 +40,47 @@ mod tests {
 
         let dt = jst.with_ymd_and_hms(2022, 5, 11, 12, 0, 0).single().unwrap();
         let opt = super::get_levels(dt.to_utc(), jst);
-        assert!(opt.is_some());
+        assert!(opt.is_ok());
         let data = opt.unwrap();
-        assert_eq!(data["level"]["燦滅のノクゼリア"], 1);
-        assert_eq!(data["term"]["from"].as_str(), Some("2022-05-11T06:00:00+09:00"));
-        assert_eq!(data["term"]["to"].as_str(), Some("2022-05-12T05:59:59+09:00"));
+        assert_eq!(data.level("NOXERIA"), Some(Level(1)));
+        assert_eq!(data.term.from.to_rfc3339(), "2022-05-11T06:00:00+09:00");
+        assert_eq!(data.term.to.to_rfc3339(), "2022-05-12T05:59:59+09:00");
 
         let dt = jst.with_ymd_and_hms(2022, 5, 13, 5, 0, 0).single().unwrap();
         let opt = super::get_levels(dt.to_utc(), jst);
-        assert!(opt.is_some());
+        assert!(opt.is_ok());
         let data = opt.unwrap();
-        assert_eq!(data["level"]["燦滅のノクゼリア"], 2);
-        assert_eq!(data["term"]["from"].as_str(), Some("2022-05-12T06:00:00+09:00"));
-        assert_eq!(data["term"]["to"].as_str(), Some("2022-05-13T05:59:59+09:00"));
+        assert_eq!(data.level("NOXERIA"), Some(Level(2)));
+        assert_eq!(data.term.from.to_rfc3339(), "2022-05-12T06:00:00+09:00");
+        assert_eq!(data.term.to.to_rfc3339(), "2022-05-13T05:59:59+09:00");
 
         let dt = Tokyo.with_ymd_and_hms(2022, 5, 13, 5, 0, 0).single().unwrap();
         let opt = super::get_levels(dt.to_utc(), Tokyo);
-        assert!(opt.is_some());
+        assert!(opt.is_ok());
         let data = opt.unwrap();
-        assert_eq!(data["level"]["燦滅のノクゼリア"], 2);
-        assert_eq!(data["term"]["from"].as_str(), Some("2022-05-12T06:00:00+09:00"));
-        assert_eq!(data["term"]["to"].as_str(), Some("2022-05-13T05:59:59+09:00"));
+        assert_eq!(data.level("NOXERIA"), Some(Level(2)));
+        assert_eq!(data.term.from.to_rfc3339(), "2022-05-12T06:00:00+09:00");
+        assert_eq!(data.term.to.to_rfc3339(), "2022-05-13T05:59:59+09:00");
+    }
+
+    #[test]
+    fn test_get_levels_term_matches_slot() {
+        // 期間はローテーションの周期と同じ
+        use crate::rotation::Rotation;
+        let dt = Utc.with_ymd_and_hms(2022, 5, 13, 5, 0, 0).single().unwrap();
+        let data = super::get_levels(dt, Utc).unwrap();
+        let occurrence = super::Sinners.current_at(dt).unwrap();
+        assert_eq!(data.term.from, occurrence.start);
+        assert_eq!(data.term.to + chrono::Duration::seconds(1), occurrence.end);
+    }
+
+    #[test]
+    fn test_get_levels_at_boundary() {
+        // JSTの6:00ちょうどは新しい期間
+        let dt = Tokyo.with_ymd_and_hms(2022, 5, 12, 6, 0, 0).single().unwrap();
+        let data = super::get_levels(dt.to_utc(), Tokyo).unwrap();
+        assert_eq!(data.level("NOXERIA"), Some(Level(2)));
+        assert_eq!(data.term.from.to_rfc3339(), "2022-05-12T06:00:00+09:00");
     }
 
     #[test]
@@ -101,20 +88,20 @@ mod tests {
         // UTCの2022/5/11 12:00:00はJSTの2022/5/11 21:00:00
         let dt = Utc.with_ymd_and_hms(2022, 5, 11, 12, 0, 0).single().unwrap();
         let opt = super::get_levels(dt, Utc);
-        assert!(opt.is_some());
+        assert!(opt.is_ok());
         let data = opt.unwrap();
-        assert_eq!(data["level"]["燦滅のノクゼリア"], 1);
-        assert_eq!(data["term"]["from"], "2022-05-10T21:00:00+00:00");
-        assert_eq!(data["term"]["to"], "2022-05-11T20:59:59+00:00");
+        assert_eq!(data.level("NOXERIA"), Some(Level(1)));
+        assert_eq!(data.term.from.to_rfc3339(), "2022-05-10T21:00:00+00:00");
+        assert_eq!(data.term.to.to_rfc3339(), "2022-05-11T20:59:59+00:00");
 
         // UTCの2022/5/13 5:00:00はJSTの2022/5/13 14:00:00
         let dt = Utc.with_ymd_and_hms(2022, 5, 13, 5, 0, 0).single().unwrap();
         let opt = super::get_levels(dt, Utc);
-        assert!(opt.is_some());
+        assert!(opt.is_ok());
         let data = opt.unwrap();
-        assert_eq!(data["level"]["燦滅のノクゼリア"], 1);
-        assert_eq!(data["term"]["from"], "2022-05-12T21:00:00+00:00");
-        assert_eq!(data["term"]["to"], "2022-05-13T20:59:59+00:00");
+        assert_eq!(data.level("NOXERIA"), Some(Level(1)));
+        assert_eq!(data.term.from.to_rfc3339(), "2022-05-12T21:00:00+00:00");
+        assert_eq!(data.term.to.to_rfc3339(), "2022-05-13T20:59:59+00:00");
     }
 
     #[test]
@@ -122,10 +109,21 @@ mod tests {
         // UTCの2022/5/11 12:00:00はSSTの2022/5/11 20:00:00
         let dt = Utc.with_ymd_and_hms(2022, 5, 11, 12, 0, 0).single().unwrap();
         let opt = super::get_levels(dt, Singapore);
-        assert!(opt.is_some());
+        assert!(opt.is_ok());
         let data = opt.unwrap();
-        assert_eq!(data["level"]["燦滅のノクゼリア"], 1);
-        assert_eq!(data["term"]["from"].as_str(), Some("2022-05-11T05:00:00+08:00"));
-        assert_eq!(data["term"]["to"].as_str(), Some("2022-05-12T04:59:59+08:00"));
+        assert_eq!(data.level("NOXERIA"), Some(Level(1)));
+        assert_eq!(data.term.from.to_rfc3339(), "2022-05-11T05:00:00+08:00");
+        assert_eq!(data.term.to.to_rfc3339(), "2022-05-12T04:59:59+08:00");
+    }
+
+    #[test]
+    fn test_get_levels_json() {
+        let dt = Utc.with_ymd_and_hms(2022, 5, 12, 0, 0, 0).single().unwrap();
+        let data = super::get_levels(dt, Tokyo).unwrap();
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["level"]["絶念のアウルモッド"], 1);
+        assert_eq!(json["level"]["狂禍のフラウソン"], 2);
+        assert_eq!(json["term"]["from"], "2022-05-12T06:00:00+09:00");
+        assert_eq!(json["term"]["to"], "2022-05-13T05:59:59+09:00");
     }
 }
@@ -0,0 +1,320 @@
+use std::thread;
+use std::time::Duration as StdDuration;
+use chrono::{DateTime, Utc};
+use chrono_tz::Asia::Tokyo;
+use serde_json::json;
+use crate::defense_force::DefenseForce;
+use crate::levels;
+use crate::notify::{Notification, Sink};
+use crate::panigarm::Panigarm;
+use crate::rotation::Rotation;
+use crate::saint_guardians::SaintGuardians;
+use crate::sinners::Sinners;
+
+/// Webhookの種類。種類によって送るJSONの形が違う
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    /// {"content": "..."}
+    Discord,
+    /// {"text": "..."}
+    Slack,
+}
+
+/// 失敗したときの再送。待ち時間は一回ごとに倍になる
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retry {
+    pub attempts: u32,
+    pub backoff: StdDuration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry { attempts: 4, backoff: StdDuration::from_secs(2) }
+    }
+}
+
+// Retry-Afterが長すぎても、この時間より長くは待たない
+const MAX_RETRY_AFTER: StdDuration = StdDuration::from_secs(60 * 60);
+
+/// 送り直す前に呼び出し元へ知らせる内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retrying {
+    /// 失敗した回。最初の送信が1
+    pub attempt: u32,
+    pub error: String,
+    /// 次に送るまでの待ち時間。Retry-Afterがあればその時間
+    pub wait: StdDuration,
+}
+
+/// WebhookのURLにメッセージをPOSTする
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub url: String,
+    pub flavor: Flavor,
+    pub retry: Retry,
+    /// 送り直す前に呼ばれる。既定では何もしない
+    pub on_retry: fn(&Retrying),
+}
+
+/// POSTが失敗した理由
+struct Failure {
+    /// 時間を置けば成功するかもしれない
+    retryable: bool,
+    /// サーバーがRetry-Afterで指定した待ち時間
+    retry_after: Option<StdDuration>,
+    message: String,
+}
+
+impl Webhook {
+    pub fn new(url: &str, flavor: Flavor) -> Webhook {
+        Webhook { url: url.to_string(), flavor, retry: Retry::default(), on_retry: |_| {} }
+    }
+
+    pub fn payload(&self, notification: &Notification) -> String {
+        let payload = match self.flavor {
+            Flavor::Discord => json!({ "content": notification.message }),
+            Flavor::Slack => json!({ "text": notification.message }),
+        };
+        payload.to_string()
+    }
+
+    fn post(&self, agent: &ureq::Agent, body: &str) -> Result<(), Failure> {
+        match agent.post(&self.url).set("Content-Type", "application/json").send_string(body) {
+            Ok(_) => Ok(()),
+            // 429と5xxは時間を置けば成功するかもしれない。HTTP日付はサーバーの実時刻なのでDQ10_NOWではなく実時刻と比べる
+            Err(ureq::Error::Status(code, response)) => Err(Failure {
+                retryable: code == 429 || code >= 500,
+                retry_after: response.header("Retry-After").and_then(|value| parse_retry_after(value, Utc::now())),
+                message: format!("{} returned {}", self.url, code),
+            }),
+            Err(ureq::Error::Transport(e)) => Err(Failure { retryable: true, retry_after: None, message: format!("{}: {}", self.url, e) }),
+        }
+    }
+}
+
+/// Retry-Afterの秒数かHTTP日付を待ち時間にする。MAX_RETRY_AFTERで頭打ちにする
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<StdDuration> {
+    let value = value.trim();
+    let wait = match value.parse::<u64>() {
+        Ok(seconds) => StdDuration::from_secs(seconds),
+        Err(_) => {
+            let at = DateTime::parse_from_rfc2822(value).ok()?;
+            (at.to_utc() - now).to_std().unwrap_or(StdDuration::ZERO)
+        }
+    };
+    Some(wait.min(MAX_RETRY_AFTER))
+}
+
+impl Sink for Webhook {
+    fn send(&self, notification: &Notification) -> Result<(), String> {
+        let agent = ureq::AgentBuilder::new().timeout(StdDuration::from_secs(10)).build();
+        let body = self.payload(notification);
+        let mut wait = self.retry.backoff;
+        let mut attempt = 1;
+        loop {
+            match self.post(&agent, &body) {
+                Ok(()) => return Ok(()),
+                Err(failure) if failure.retryable && attempt < self.retry.attempts => {
+                    let this_wait = failure.retry_after.unwrap_or(wait);
+                    (self.on_retry)(&Retrying { attempt, error: failure.message, wait: this_wait });
+                    thread::sleep(this_wait);
+                    wait = wait.saturating_mul(2);
+                    attempt += 1;
+                }
+                Err(failure) => return Err(failure.message),
+            }
+        }
+    }
+}
+
+/// 送信せずに、送る内容を標準出力に表示する
+pub struct DryRun(pub Webhook);
+
+impl Sink for DryRun {
+    fn send(&self, notification: &Notification) -> Result<(), String> {
+        println!("POST {}\n{}", self.0.url, self.0.payload(notification));
+        Ok(())
+    }
+}
+
+/// 指定時刻の後で、いずれかのコンテンツが次に切り替わる時刻
+pub fn next_change(dt: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    [
+        DefenseForce.next_change_after(dt),
+        Panigarm.next_change_after(dt),
+        Sinners.next_change_after(dt),
+        SaintGuardians.next_change_after(dt),
+    ].into_iter().flatten().min()
+}
+
+/// 指定時刻ちょうどに切り替わったコンテンツのお知らせ。一行に一つ
+pub fn announcements_at(dt: DateTime<Utc>) -> Vec<String> {
+    status(dt).into_iter().filter(|(start, _)| *start == dt).map(|(_, line)| line).collect()
+}
+
+/// 指定時刻のすべてのコンテンツの状態。一行に一つ
+pub fn status_at(dt: DateTime<Utc>) -> Vec<String> {
+    status(dt).into_iter().map(|(_, line)| line).collect()
+}
+
+fn status(dt: DateTime<Utc>) -> Vec<(DateTime<Utc>, String)> {
+    [
+        line("防衛軍", &DefenseForce, dt, |troop| troop.name().to_string()),
+        line("パニガルム", &Panigarm, dt, |boss| boss.name.to_string()),
+        line("アビスの咎人", &Sinners, dt, |levels| levels::format_levels(levels)),
+        line("聖守護者", &SaintGuardians, dt, |levels| levels::format_levels(levels)),
+    ].into_iter().flatten().collect()
+}
+
+/// 時刻はアストルティアの基準であるJSTで表す
+fn line<R: Rotation>(label: &str, rotation: &R, dt: DateTime<Utc>, format: impl Fn(&R::Item) -> String) -> Option<(DateTime<Utc>, String)> {
+    let current = rotation.current_at(dt)?;
+    let end = current.end.with_timezone(&Tokyo).format("%m/%d %H:%M");
+    Some((current.start, format!("{}: {} ({}まで)", label, format(&current.value), end)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{mpsc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use chrono::{TimeZone, Utc};
+    use chrono_tz::Asia::Tokyo;
+    use super::{Flavor, Retry, Retrying, Webhook};
+    use crate::notify::{Notification, Sink};
+
+    /// 決められたステータスを順に返すHTTPサーバー。受け取ったボディをチャンネルに送る
+    fn stand_in(statuses: Vec<u16>) -> (String, mpsc::Receiver<String>) {
+        stand_in_with_headers(statuses.into_iter().map(|status| (status, "")).collect())
+    }
+
+    /// stand_inと同じで、ステータスごとにヘッダーを加える。ヘッダーは"Name: value\r\n"の形
+    fn stand_in_with_headers(responses: Vec<(u16, &'static str)>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/webhook", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for (status, headers) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header == "\r\n" {
+                        break;
+                    }
+                    if let Some(value) = header.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                tx.send(String::from_utf8(body).unwrap()).unwrap();
+                let response = format!("HTTP/1.1 {} X\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n", status, headers);
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    fn notification() -> Notification {
+        let start = Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap();
+        Notification {
+            title: "dq10tools".to_string(),
+            message: "防衛軍: 金神の遺宝兵団 (12/10 07:00まで)".to_string(),
+            start,
+            end: start + chrono::Duration::hours(1),
+        }
+    }
+
+    fn webhook(url: &str, flavor: Flavor) -> Webhook {
+        Webhook { retry: Retry { attempts: 3, backoff: Duration::from_millis(1) }, ..Webhook::new(url, flavor) }
+    }
+
+    #[test]
+    fn test_discord_payload() {
+        let (url, rx) = stand_in(vec![204]);
+        assert_eq!(webhook(&url, Flavor::Discord).send(&notification()), Ok(()));
+        let body: serde_json::Value = serde_json::from_str(&rx.recv().unwrap()).unwrap();
+        assert_eq!(body["content"], "防衛軍: 金神の遺宝兵団 (12/10 07:00まで)");
+    }
+
+    #[test]
+    fn test_slack_payload() {
+        let (url, rx) = stand_in(vec![200]);
+        assert_eq!(webhook(&url, Flavor::Slack).send(&notification()), Ok(()));
+        let body: serde_json::Value = serde_json::from_str(&rx.recv().unwrap()).unwrap();
+        assert_eq!(body["text"], "防衛軍: 金神の遺宝兵団 (12/10 07:00まで)");
+    }
+
+    #[test]
+    fn test_retry_on_server_error() {
+        let (url, rx) = stand_in(vec![500, 429, 204]);
+        assert_eq!(webhook(&url, Flavor::Discord).send(&notification()), Ok(()));
+        assert_eq!(rx.try_iter().count(), 3);
+    }
+
+    #[test]
+    fn test_give_up_after_attempts() {
+        let (url, rx) = stand_in(vec![503, 503, 503]);
+        assert!(webhook(&url, Flavor::Discord).send(&notification()).is_err());
+        assert_eq!(rx.try_iter().count(), 3);
+    }
+
+    #[test]
+    fn test_report_retries() {
+        static RETRIES: Mutex<Vec<Retrying>> = Mutex::new(Vec::new());
+        let (url, _rx) = stand_in_with_headers(vec![(503, ""), (429, "Retry-After: 1\r\n"), (204, "")]);
+        let webhook = Webhook { on_retry: |retrying| RETRIES.lock().unwrap().push(retrying.clone()), ..webhook(&url, Flavor::Discord) };
+        let started = Instant::now();
+        assert_eq!(webhook.send(&notification()), Ok(()));
+        // 429のRetry-Afterは既定の間隔より優先する
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        let retries = RETRIES.lock().unwrap();
+        assert_eq!(retries.len(), 2);
+        assert_eq!((retries[0].attempt, retries[0].wait), (1, Duration::from_millis(1)));
+        assert!(retries[0].error.ends_with("returned 503"));
+        assert_eq!((retries[1].attempt, retries[1].wait), (2, Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap();
+        assert_eq!(super::parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(super::parse_retry_after("Tue, 09 Dec 2025 21:00:30 GMT", now), Some(Duration::from_secs(30)));
+        assert_eq!(super::parse_retry_after("Tue, 09 Dec 2025 20:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(super::parse_retry_after("99999999", now), Some(super::MAX_RETRY_AFTER));
+        assert_eq!(super::parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn test_no_retry_on_client_error() {
+        let (url, rx) = stand_in(vec![404]);
+        assert!(webhook(&url, Flavor::Discord).send(&notification()).is_err());
+        assert_eq!(rx.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_announcements_at_level_reset() {
+        // JSTの6:00は防衛軍とアビスの咎人、聖守護者のレベルが切り替わる。パニガルムは3日ごと
+        let dt = Tokyo.with_ymd_and_hms(2025, 12, 11, 6, 0, 0).unwrap().to_utc();
+        let lines = super::announcements_at(dt);
+        assert!(lines[0].starts_with("防衛軍: "));
+        assert!(lines[1].starts_with("アビスの咎人: "));
+        assert!(lines[1].ends_with("(12/12 06:00まで)"));
+        assert!(lines[2].starts_with("聖守護者: "));
+        assert!(lines[2].ends_with("(12/12 06:00まで)"));
+        assert!(!lines.iter().any(|line| line.starts_with("パニガルム: ")));
+    }
+
+    #[test]
+    fn test_next_change() {
+        let dt = Tokyo.with_ymd_and_hms(2025, 12, 11, 5, 30, 0).unwrap().to_utc();
+        assert_eq!(super::next_change(dt), Some(Tokyo.with_ymd_and_hms(2025, 12, 11, 6, 0, 0).unwrap().to_utc()));
+        assert_eq!(super::status_at(dt).len(), 4);
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::str::FromStr;
+use chrono::{FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime, Offset, TimeZone};
+use chrono_tz::Tz;
+
+/// 時刻を表すタイムゾーン。実行環境のローカル時刻か、"Asia/Tokyo"のようなIANAのタイムゾーン
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Zone {
+    #[default]
+    Local,
+    Named(Tz),
+}
+
+/// Zoneの時差
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoneOffset {
+    Local(FixedOffset),
+    Named(<Tz as TimeZone>::Offset),
+}
+
+impl Offset for ZoneOffset {
+    fn fix(&self) -> FixedOffset {
+        match self {
+            ZoneOffset::Local(offset) => *offset,
+            ZoneOffset::Named(offset) => offset.fix(),
+        }
+    }
+}
+
+/// "%Z"での表示。ローカル時刻は"+09:00"、IANAのタイムゾーンは"JST"のような略称
+impl fmt::Display for ZoneOffset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ZoneOffset::Local(offset) => offset.fmt(f),
+            ZoneOffset::Named(offset) => offset.fmt(f),
+        }
+    }
+}
+
+impl TimeZone for Zone {
+    type Offset = ZoneOffset;
+
+    fn from_offset(offset: &ZoneOffset) -> Zone {
+        match offset {
+            ZoneOffset::Local(_) => Zone::Local,
+            ZoneOffset::Named(offset) => Zone::Named(Tz::from_offset(offset)),
+        }
+    }
+
+    #[allow(deprecated)]
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<ZoneOffset> {
+        match self {
+            Zone::Local => Local.offset_from_local_date(local).map(ZoneOffset::Local),
+            Zone::Named(tz) => tz.offset_from_local_date(local).map(ZoneOffset::Named),
+        }
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<ZoneOffset> {
+        match self {
+            Zone::Local => Local.offset_from_local_datetime(local).map(ZoneOffset::Local),
+            Zone::Named(tz) => tz.offset_from_local_datetime(local).map(ZoneOffset::Named),
+        }
+    }
+
+    #[allow(deprecated)]
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> ZoneOffset {
+        match self {
+            Zone::Local => ZoneOffset::Local(Local.offset_from_utc_date(utc)),
+            Zone::Named(tz) => ZoneOffset::Named(tz.offset_from_utc_date(utc)),
+        }
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> ZoneOffset {
+        match self {
+            Zone::Local => ZoneOffset::Local(Local.offset_from_utc_datetime(utc)),
+            Zone::Named(tz) => ZoneOffset::Named(tz.offset_from_utc_datetime(utc)),
+        }
+    }
+}
+
+/// "local"または"Asia/Tokyo"のようなIANAのタイムゾーン名
+impl FromStr for Zone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Zone, String> {
+        if s.eq_ignore_ascii_case("local") {
+            return Ok(Zone::Local);
+        }
+        s.parse::<Tz>().map(Zone::Named).map_err(|_| format!("unknown time zone {}. use an IANA name such as Asia/Tokyo, or local", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone, Utc};
+    use chrono_tz::Asia::Tokyo;
+    use super::Zone;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("Asia/Tokyo".parse(), Ok(Zone::Named(Tokyo)));
+        assert_eq!("local".parse(), Ok(Zone::Local));
+        assert!("Asia/Nowhere".parse::<Zone>().is_err());
+    }
+
+    #[test]
+    fn test_with_timezone() {
+        let dt = Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap();
+        let jst = dt.with_timezone(&Zone::Named(Tokyo));
+        assert_eq!(jst.to_rfc3339(), "2025-12-10T06:00:00+09:00");
+        assert_eq!(jst.format("%H:%M %Z").to_string(), "06:00 JST");
+        assert_eq!(dt.with_timezone(&Zone::Local).naive_local(), dt.with_timezone(&Local).naive_local());
+        assert_eq!(Zone::Named(Tokyo).with_ymd_and_hms(2025, 12, 10, 6, 0, 0).unwrap(), dt);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let dt = Utc.with_ymd_and_hms(2025, 12, 9, 21, 0, 0).unwrap().with_timezone(&Zone::Named(Tokyo));
+        assert_eq!(serde_json::to_string(&dt).unwrap(), "\"2025-12-10T06:00:00+09:00\"");
+    }
+}